@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(name = "kumpun")]
@@ -13,20 +13,96 @@ pub enum Commands {
     Dev,
     Check,
     Docs,
-    Generate {
-        #[arg(short, long)]
-        schema: String,
+    Generate(Box<GenerateArgs>),
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    /// Schema name without `.json`, relative to `--schema-dir`. Repeat it or use a glob
+    /// (`*`, `?`, `**`) to generate several schemas in one run
+    #[arg(short, long, required_unless_present = "all")]
+    pub schema: Vec<String>,
+
+    /// Generate every schema directly in `--schema-dir`, same as `--schema '*'`
+    #[arg(long, conflicts_with = "schema")]
+    pub all: bool,
+
+    #[arg(short, long, default_value = "rust")]
+    pub target: String,
+
+    #[arg(long, default_value = "schemas")]
+    pub schema_dir: String,
+
+    #[arg(long, default_value = "generated")]
+    pub out_dir: String,
+
+    #[arg(long)]
+    pub with_docs: bool,
+
+    /// Keep `format` strings as plain `String` instead of mapping them to rich types
+    #[arg(long)]
+    pub no_format_types: bool,
+
+    /// Override one entry of the format-to-type table, e.g. `date-time=String`
+    #[arg(long = "format-type", value_name = "FORMAT=TYPE")]
+    pub format_types: Vec<String>,
+
+    /// Derive `validator::Validate` from schema constraints such as `minLength` or `pattern`
+    #[arg(long)]
+    pub with_validation: bool,
+
+    /// Add an `Unknown` fallback variant to every generated `enum`
+    #[arg(long)]
+    pub enum_fallback: bool,
+
+    /// Merge `allOf` parts into a single struct instead of `#[serde(flatten)]`-ed part structs
+    #[arg(long)]
+    pub merge_all_of: bool,
+
+    /// Collection generated for `uniqueItems: true` arrays: `btree` (`BTreeSet`),
+    /// `hash` (`HashSet`) or `vec` to keep `Vec`
+    #[arg(long, default_value = "btree", value_parser = ["btree", "hash", "vec"])]
+    pub set_type: String,
+
+    /// Emit `impl Default` for structs, using schema `default`s where present
+    #[arg(long)]
+    pub with_default: bool,
+
+    /// Emit a typed builder (`Type::builder().field(..).build()?`) for structs
+    #[arg(long)]
+    pub with_builder: bool,
+
+    /// Wrap strings and numbers with `pattern`, `format`, length or range constraints in
+    /// newtypes whose `TryFrom`/`Deserialize` reject invalid values
+    #[arg(long)]
+    pub with_newtypes: bool,
+
+    /// Extra traits every generated type derives, e.g. `Clone,PartialEq`
+    #[arg(long = "derive", value_name = "TRAITS", value_delimiter = ',')]
+    pub derives: Vec<String>,
+
+    /// Rust path of the `--out-dir` module used in imports between generated files, e.g.
+    /// `crate::api::types`. Derived from `--out-dir` when omitted: `src/api/types` →
+    /// `crate::api::types`, anything outside `src/` → `crate::{last folder}`
+    #[arg(long, value_name = "PATH")]
+    pub module_path: Option<String>,
 
-        #[arg(short, long, default_value = "rust")]
-        target: String,
+    /// Import between generated files with `super::` paths, so the output can be moved
+    #[arg(long)]
+    pub relative_imports: bool,
 
-        #[arg(long, default_value = "schemas")]
-        schema_dir: String,
+    /// Write a standalone Cargo crate named NAME to `--out-dir`: a `Cargo.toml` listing the
+    /// dependencies the generated code uses, `src/lib.rs` and the module tree
+    #[arg(long = "crate", value_name = "NAME")]
+    pub crate_name: Option<String>,
 
-        #[arg(long, default_value = "generated")]
-        out_dir: String,
+    /// Version of the `--crate`, by default the `version` of the schema registry
+    /// (`index.json` in `--schema-dir`)
+    #[arg(long, value_name = "VERSION", requires = "crate_name")]
+    pub crate_version: Option<String>,
 
-        #[arg(long)]
-        with_docs: bool,
-    },
+    /// Resolve absolute `$ref`/`$id` URIs starting with PREFIX from PATH under the schema dir,
+    /// e.g. `https://json-schema.org/draft/2020-12/=meta/2020-12`
+    #[arg(long = "map-uri", value_name = "PREFIX=PATH")]
+    pub uri_map: Vec<String>,
 }
@@ -1,42 +1,76 @@
 pub mod schema_to_rust;
 
-use crate::{log_debug, log_error};
+use crate::kumpun::args::cli::GenerateArgs;
+use crate::{log_debug, log_error, log_warn};
+use schema_to_rust::GeneratorOptions;
+use schema_to_rust::NamedStruct;
 use schema_to_rust::RefResolver;
+use schema_to_rust::SetType;
+use schema_to_rust::TypeRegistry;
+use schema_to_rust::default_format_types;
+use schema_to_rust::generate_mod_rs_recursively;
 use schema_to_rust::generate_rust_structs_from_schema;
 use schema_to_rust::to_pascal_case;
-use schema_to_rust::write_named_structs;
+use schema_to_rust::to_snake_case;
+use schema_to_rust::write_module_files;
+use schema_to_rust::write_root_file;
+use std::collections::BTreeSet;
 use std::fs;
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 pub fn init() {
     log_debug!("🛠️ [generate] stub generator module initialized");
 }
 
-pub fn run(schema: &str, target: &str, schema_dir: &str, out_dir: &str, with_docs: &bool) {
+pub fn run(args: &GenerateArgs) {
+    let GenerateArgs {
+        target,
+        schema_dir,
+        out_dir,
+        with_docs,
+        ..
+    } = args;
+
+    let schema_names = collect_schema_names(args);
     log_debug!(
-        "🛠️ Generating for schema: '{}', target: '{}'",
-        schema,
+        "🛠️ Generating for schemas: {:?}, target: '{}'",
+        schema_names,
         target
     );
 
-    let schema_path = build_schema_path(schema_dir, schema);
-    if !schema_path.exists() {
-        log_error!("❌ Schema file not found: {}", schema_path.display());
-        std::process::exit(1);
-    }
+    let schemas = schema_names
+        .into_iter()
+        .map(|name| {
+            let schema_str = read_schema(schema_dir, &name);
+            (name, schema_str)
+        })
+        .collect::<Vec<_>>();
 
-    let schema_str = match fs::read_to_string(&schema_path) {
-        Ok(content) => content,
-        Err(e) => {
-            log_error!("❌ Failed to read schema: {}", e);
+    match target.as_str() {
+        "typescript" => {
+            for (schema, schema_str) in &schemas {
+                generate_typescript_stub(schema, schema_str, out_dir);
+            }
+        }
+        "rust" => {
+            let options = build_generator_options(args);
+            let mut resolver = build_ref_resolver(args);
+            let module_dir = build_module_dir(args);
+            if args.crate_name.is_some() {
+                // A `lib.rs` left by an earlier run would be listed as `pub mod lib;`
+                let _ = fs::remove_file(Path::new(&module_dir).join("lib.rs"));
+            }
+            let (structs, crates) =
+                generate_rust_stubs(&schemas, &mut resolver, &module_dir, with_docs, &options);
+            if let Some(crate_name) = &args.crate_name {
+                write_crate(args, crate_name, &structs, &crates);
+            }
+        }
+        _ if args.crate_name.is_some() => {
+            log_error!("❌ --crate only supports the rust target");
             std::process::exit(1);
         }
-    };
-
-    match target {
-        "typescript" => generate_typescript_stub(schema, &schema_str, out_dir),
-        "rust" => generate_rust_stub(schema, schema_dir, &schema_str, out_dir, with_docs),
         _ => {
             log_error!("❌ Unsupported target: {}", target);
             std::process::exit(1);
@@ -44,11 +78,212 @@ pub fn run(schema: &str, target: &str, schema_dir: &str, out_dir: &str, with_doc
     }
 }
 
+/// The schemas to generate, in order: each `--schema`, where a glob stands for every
+/// matching `.json` under `--schema-dir`, or every schema directly in it for `--all`.
+fn collect_schema_names(args: &GenerateArgs) -> Vec<String> {
+    let patterns = if args.all {
+        vec!["*".to_string()]
+    } else {
+        args.schema.clone()
+    };
+
+    let mut available = None;
+    let mut names: Vec<String> = vec![];
+    for pattern in &patterns {
+        if !pattern.contains(['*', '?']) {
+            names.push(pattern.clone());
+            continue;
+        }
+        let available = available.get_or_insert_with(|| list_schemas(&args.schema_dir));
+        let pattern = pattern.chars().collect::<Vec<_>>();
+        let matched = available
+            .iter()
+            .filter(|name| glob_match(&pattern, &name.chars().collect::<Vec<_>>()))
+            .cloned()
+            .collect::<Vec<_>>();
+        if matched.is_empty() {
+            log_error!(
+                "❌ No schema in {} matches '{}'",
+                args.schema_dir,
+                pattern.iter().collect::<String>()
+            );
+            std::process::exit(1);
+        }
+        names.extend(matched);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+    names
+}
+
+/// Every `.json` file under `schema_dir` as a schema name: relative, `/`-separated and
+/// without the extension, sorted.
+fn list_schemas(schema_dir: &str) -> Vec<String> {
+    fn walk(dir: &Path, prefix: &str, names: &mut Vec<String>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if path.is_dir() {
+                walk(&path, &format!("{}{}/", prefix, file_name), names);
+            } else if let Some(stem) = file_name.strip_suffix(".json") {
+                // The schema registry lists schemas, it isn't one
+                if prefix.is_empty() && stem == REGISTRY_FILE.trim_end_matches(".json") {
+                    continue;
+                }
+                names.push(format!("{}{}", prefix, stem));
+            }
+        }
+    }
+
+    let mut names = vec![];
+    walk(Path::new(schema_dir), "", &mut names);
+    names.sort();
+    names
+}
+
+/// Shell-style match: `*` and `?` stay within one path segment, `**` crosses them.
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern {
+        [] => name.is_empty(),
+        // `**/` also matches no folder at all
+        ['*', '*', '/', rest @ ..] => {
+            glob_match(rest, name)
+                || (0..name.len()).any(|i| name[i] == '/' && glob_match(rest, &name[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=name.len()).any(|i| glob_match(rest, &name[i..])),
+        ['*', rest @ ..] => (0..=name.len())
+            .take_while(|&i| i == 0 || name[i - 1] != '/')
+            .any(|i| glob_match(rest, &name[i..])),
+        ['?', rest @ ..] => matches!(name, [c, ..] if *c != '/') && glob_match(rest, &name[1..]),
+        [c, rest @ ..] => name.first() == Some(c) && glob_match(rest, &name[1..]),
+    }
+}
+
+fn build_generator_options(args: &GenerateArgs) -> GeneratorOptions {
+    let mut format_types = if args.no_format_types {
+        Default::default()
+    } else {
+        default_format_types()
+    };
+
+    for entry in &args.format_types {
+        match entry.split_once('=') {
+            Some((format, rust_type)) if !format.is_empty() && !rust_type.is_empty() => {
+                format_types.insert(format.trim().to_string(), rust_type.trim().to_string());
+            }
+            _ => {
+                log_error!("❌ Invalid --format-type '{}', expected FORMAT=TYPE", entry);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    GeneratorOptions {
+        format_types,
+        with_validation: args.with_validation,
+        enum_fallback: args.enum_fallback,
+        merge_all_of: args.merge_all_of,
+        set_type: match args.set_type.as_str() {
+            "hash" => SetType::Hash,
+            "vec" => SetType::Vec,
+            _ => SetType::BTree,
+        },
+        with_default: args.with_default,
+        with_builder: args.with_builder,
+        with_newtypes: args.with_newtypes,
+        derives: args
+            .derives
+            .iter()
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty())
+            .collect(),
+        module_path: build_module_path(args),
+        relative_imports: args.relative_imports,
+    }
+}
+
+/// Folder the generated modules go to: `--out-dir`, or its `src/` for `--crate`.
+fn build_module_dir(args: &GenerateArgs) -> String {
+    match args.crate_name {
+        Some(_) => Path::new(&args.out_dir)
+            .join("src")
+            .to_string_lossy()
+            .to_string(),
+        None => args.out_dir.clone(),
+    }
+}
+
+/// `--module-path`, or the module path of the generated folder: what follows its last
+/// `src` folder (`src/api/types` → `crate::api::types`), else its last folder.
+fn build_module_path(args: &GenerateArgs) -> String {
+    if let Some(path) = &args.module_path {
+        return path.trim_end_matches("::").to_string();
+    }
+
+    let module_dir = build_module_dir(args);
+    let folders = Path::new(&module_dir)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let modules = match folders.iter().rposition(|folder| *folder == "src") {
+        Some(src) => &folders[src + 1..],
+        None => &folders[folders.len().saturating_sub(1)..],
+    };
+    std::iter::once("crate".to_string())
+        .chain(modules.iter().map(|m| to_snake_case(m)))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn build_ref_resolver(args: &GenerateArgs) -> RefResolver {
+    let mut resolver = RefResolver::new(&args.schema_dir);
+
+    for entry in &args.uri_map {
+        // Split on the last `=` so the URI prefix itself may contain one
+        match entry.rsplit_once('=') {
+            Some((prefix, path)) if !prefix.is_empty() && !path.is_empty() => {
+                resolver.map_uri(prefix.trim(), path.trim());
+            }
+            _ => {
+                log_error!("❌ Invalid --map-uri '{}', expected PREFIX=PATH", entry);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    resolver
+}
+
 fn build_schema_path(schema_dir: &str, schema: &str) -> PathBuf {
     let root = std::env::current_dir().expect("Failed to get current dir");
     root.join(schema_dir).join(format!("{}.json", schema))
 }
 
+fn read_schema(schema_dir: &str, schema: &str) -> String {
+    let schema_path = build_schema_path(schema_dir, schema);
+    if !schema_path.exists() {
+        log_error!("❌ Schema file not found: {}", schema_path.display());
+        std::process::exit(1);
+    }
+
+    match fs::read_to_string(&schema_path) {
+        Ok(content) => content,
+        Err(e) => {
+            log_error!("❌ Failed to read schema: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn write_to_file(schema_name: &str, ext: &str, content: &str, out_dir: &str) {
     let out_path = Path::new(out_dir).join(format!("{}.{}", schema_name, ext));
     let parent = out_path.parent().unwrap();
@@ -69,26 +304,158 @@ fn generate_typescript_stub(schema_name: &str, _schema: &str, out_dir: &str) {
     write_to_file(schema_name, "ts", &interface, out_dir);
 }
 
-fn generate_rust_stub(
-    schema_name: &str,
-    schema_dir: &str,
-    schema_str: &str,
+/// Generates every schema in one pass: they share the resolver cache and a
+/// `TypeRegistry`, so a type several schemas `$ref` is generated and written once.
+///
+/// Returns the generated items and the crates their paths name.
+fn generate_rust_stubs(
+    schemas: &[(String, String)],
+    resolver: &mut RefResolver,
     out_dir: &str,
     with_docs: &bool,
+    options: &GeneratorOptions,
+) -> (Vec<NamedStruct>, BTreeSet<String>) {
+    let mut registry = TypeRegistry::default();
+    let mut roots = vec![];
+
+    for (schema_name, schema_str) in schemas {
+        // 1. Parse schema
+        let schema: serde_json::Value =
+            serde_json::from_str(schema_str).expect("Invalid JSON Schema");
+
+        // 2. แปลงชื่อ schema เป็น struct name เช่น user.login → UserLogin
+        let root_struct_name = to_pascal_case(schema_name);
+
+        // 3. Register the root document so its `$id` and relative refs resolve
+        let base = resolver.register(&format!("{}.json", schema_name), &schema);
+
+        // 4. Generate all structs
+        let structs = match generate_rust_structs_from_schema(
+            &root_struct_name,
+            &schema,
+            base,
+            resolver,
+            with_docs,
+            options,
+            &mut registry,
+        ) {
+            Ok(structs) => structs,
+            Err(errors) => {
+                for error in errors {
+                    log_error!("{}", error);
+                }
+                std::process::exit(1);
+            }
+        };
+        roots.push((schema_name, structs));
+    }
+
+    // 5. Write each schema's own file, then the shared types once
+    for (schema_name, structs) in &roots {
+        write_root_file(structs, out_dir, schema_name);
+    }
+    write_module_files(&registry.shared, out_dir);
+    generate_mod_rs_recursively(Path::new(out_dir)).expect("Failed to generate mod.rs files");
+
+    let structs = roots
+        .into_iter()
+        .flat_map(|(_, structs)| structs)
+        .filter(|s| s.output_path.is_none())
+        .chain(registry.shared)
+        .collect();
+    (structs, registry.crates)
+}
+
+/// The schema registry: an optional `index.json` in the schema dir describing the schemas
+/// as a whole, e.g. `{ "version": "1.4.0" }`.
+const REGISTRY_FILE: &str = "index.json";
+
+/// Crates the generated code may use: `(crate, what shows it's used, Cargo.toml entry)`.
+const CRATE_DEPENDENCIES: &[(&str, &str, &str)] = &[
+    (
+        "serde",
+        "Serialize",
+        r#"{ version = "1", features = ["derive"] }"#,
+    ),
+    ("serde_json", "serde_json::", r#""1""#),
+    (
+        "chrono",
+        "chrono::",
+        r#"{ version = "0.4", features = ["serde"] }"#,
+    ),
+    (
+        "uuid",
+        "uuid::",
+        r#"{ version = "1", features = ["serde"] }"#,
+    ),
+    ("url", "url::", r#"{ version = "2", features = ["serde"] }"#),
+    (
+        "validator",
+        "Validate",
+        r#"{ version = "0.20", features = ["derive"] }"#,
+    ),
+    ("regex", "regex::", r#""1""#),
+];
+
+/// Turns the generated `src/` into a crate: its `mod.rs` becomes `lib.rs`, next to a
+/// `Cargo.toml` listing only the dependencies the generated code uses.
+///
+/// `crates` are the crates named by paths in the generated types; one outside
+/// `CRATE_DEPENDENCIES` (e.g. from an `x-rust-type`) can't be listed and is warned about.
+fn write_crate(
+    args: &GenerateArgs,
+    crate_name: &str,
+    structs: &[NamedStruct],
+    crates: &BTreeSet<String>,
 ) {
-    // 1. Parse schema
-    let schema: serde_json::Value = serde_json::from_str(schema_str).expect("Invalid JSON Schema");
+    let src = Path::new(&args.out_dir).join("src");
+    fs::rename(src.join("mod.rs"), src.join("lib.rs")).expect("Failed to write lib.rs");
 
-    // 2. แปลงชื่อ schema เป็น struct name เช่น user.login → UserLogin
-    let root_struct_name = to_pascal_case(schema_name);
+    let version = args
+        .crate_version
+        .clone()
+        .or_else(|| registry_version(&args.schema_dir))
+        .unwrap_or_else(|| {
+            log_warn!(
+                "⚠️ No `version` in {}/{}, using 0.1.0",
+                args.schema_dir,
+                REGISTRY_FILE
+            );
+            "0.1.0".to_string()
+        });
 
-    // 3. Prepare RefResolver
-    let mut resolver = RefResolver::new(schema_dir);
+    let code = structs
+        .iter()
+        .flat_map(|s| std::iter::once(&s.code).chain(&s.imports))
+        .map(|code| code.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let dependencies = CRATE_DEPENDENCIES
+        .iter()
+        .filter(|(name, marker, _)| crates.contains(*name) || code.contains(marker))
+        .map(|(name, _, entry)| format!("{} = {}\n", name, entry))
+        .collect::<String>();
+    for name in crates {
+        let known = ["std", "core", "alloc", "crate", "self", "super"].contains(&name.as_str())
+            || CRATE_DEPENDENCIES.iter().any(|(dep, _, _)| dep == name);
+        if !known {
+            log_warn!(
+                "⚠️ The generated code uses `{}`, which isn't in Cargo.toml; add it as a dependency",
+                name
+            );
+        }
+    }
 
-    // 4. Generate all structs
-    let structs =
-        generate_rust_structs_from_schema(&root_struct_name, &schema, &mut resolver, with_docs);
+    let manifest = format!(
+        "[package]\nname = \"{}\"\nversion = \"{}\"\nedition = \"2024\"\ndescription = \"API types generated by kumpun\"\n\n[dependencies]\n{}",
+        crate_name, version, dependencies
+    );
+    write_to_file("Cargo", "toml", &manifest, &args.out_dir);
+}
 
-    // 5. Write all structs to files
-    write_named_structs(&structs, out_dir, schema_name);
+/// `version` of the schema registry in `schema_dir`, if there is one.
+fn registry_version(schema_dir: &str) -> Option<String> {
+    let registry = fs::read_to_string(Path::new(schema_dir).join(REGISTRY_FILE)).ok()?;
+    let registry: serde_json::Value = serde_json::from_str(&registry).ok()?;
+    Some(registry.get("version")?.as_str()?.to_string())
 }
@@ -1,5 +1,6 @@
-use crate::log_debug;
+use crate::{log_debug, log_warn};
 use serde_json::Value;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs;
@@ -12,111 +13,776 @@ pub struct NamedStruct {
     pub name: String,
     pub code: String,
     pub output_path: Option<String>,
+    /// `use` lines this item needs when written to its own file
+    pub imports: Vec<String>,
+    /// Rust types this item holds (fields, variant payloads), used to decide which std
+    /// traits it can derive when it ends up inside a set
+    pub members: Vec<String>,
+}
+
+/// Options that shape the generated code, shared by every type in one run.
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    /// `format` value → Rust type path used for `type: string` properties
+    pub format_types: HashMap<String, String>,
+    /// Derive `validator::Validate` and translate schema constraints into `#[validate(...)]`
+    pub with_validation: bool,
+    /// Add an `Unknown` variant so values added to an `enum` later still deserialize
+    pub enum_fallback: bool,
+    /// Combine `allOf` parts into one struct instead of flattening a struct per part
+    pub merge_all_of: bool,
+    /// Collection used for `uniqueItems: true` arrays
+    pub set_type: SetType,
+    /// Emit `impl Default` for structs, using schema defaults where present
+    pub with_default: bool,
+    /// Emit a `{Struct}Builder` with `{Struct}::builder()` for structs
+    pub with_builder: bool,
+    /// Wrap constrained strings and numbers in validating newtypes (`pub struct Email(String)`)
+    pub with_newtypes: bool,
+    /// Extra traits derived by every generated type (`--derive`)
+    pub derives: Vec<String>,
+    /// Rust path of the out dir module (`crate::generated`), used by imports between
+    /// generated files
+    pub module_path: String,
+    /// Import between generated files with `super::` paths instead of `module_path`
+    pub relative_imports: bool,
+}
+
+/// Collection generated for `uniqueItems: true` arrays.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SetType {
+    /// `BTreeSet<T>`: needs `Ord` on `T`, keeps a stable order
+    #[default]
+    BTree,
+    /// `HashSet<T>`: needs `Hash` on `T`
+    Hash,
+    /// Keep `Vec<T>`
+    Vec,
+}
+
+/// Built-in mapping from string `format`s to Rust types.
+pub fn default_format_types() -> HashMap<String, String> {
+    [
+        ("uuid", "uuid::Uuid"),
+        ("date-time", "chrono::DateTime<chrono::Utc>"),
+        ("date", "chrono::NaiveDate"),
+        ("time", "chrono::NaiveTime"),
+        ("uri", "url::Url"),
+        ("ipv4", "std::net::IpAddr"),
+        ("ipv6", "std::net::IpAddr"),
+    ]
+    .into_iter()
+    .map(|(format, rust_type)| (format.to_string(), rust_type.to_string()))
+    .collect()
+}
+
+/// Where a schema's `$ref`s are resolved from: its base URI (the nearest `$id`, or the
+/// file itself when there is none) and the file it was loaded from, relative to the schema dir.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaBase {
+    pub uri: String,
+    pub file: String,
+}
+
+/// A schema resource loaded for an external `$ref`.
+pub struct ResolvedRef {
+    /// The resource the ref's URI names; its own `#...` fragments resolve inside it
+    pub resource: Value,
+    pub base: SchemaBase,
 }
 
 pub struct RefResolver {
     base_path: PathBuf,
+    /// Absolute URI prefix → local path under `base_path`, longest prefix first
+    uri_map: Vec<(String, String)>,
+    /// Loaded documents, keyed by file path relative to `base_path`
     cache: HashMap<String, Value>,
+    /// Every `$id` declared in a loaded document → file and JSON pointer of the declaring schema
+    ids: HashMap<String, (String, String)>,
 }
 
 impl RefResolver {
     pub fn new(base_path: impl Into<PathBuf>) -> Self {
         Self {
             base_path: base_path.into(),
+            uri_map: vec![],
             cache: HashMap::new(),
+            ids: HashMap::new(),
         }
     }
 
-    pub fn resolve(&mut self, ref_str: &str) -> Option<Value> {
-        if ref_str.starts_with("#/") {
-            return None;
-        }
+    /// Resolves absolute URIs starting with `prefix` from files under `path` instead of the network,
+    /// e.g. `https://json-schema.org/draft/2020-12/` → `metaschemas/2020-12`.
+    pub fn map_uri(&mut self, prefix: impl Into<String>, path: impl Into<String>) {
+        self.uri_map.push((prefix.into(), path.into()));
+        self.uri_map
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    }
 
-        let parts: Vec<&str> = ref_str.split('#').collect();
-        let file_path = parts[0];
-        let pointer = parts.get(1).map(|s| *s).unwrap_or("");
+    /// Registers an already-parsed document, such as the root schema, and returns its base.
+    pub fn register(&mut self, file: &str, document: &Value) -> SchemaBase {
+        let file = normalize_uri(file);
+        self.cache.insert(file.clone(), document.clone());
+        self.index_ids(&file, document, &file, "");
+        SchemaBase {
+            uri: declared_base(document, &file).unwrap_or_else(|| file.clone()),
+            file,
+        }
+    }
 
-        let full_path = self.base_path.join(file_path);
-        log_debug!("📂 Looking for: {}", full_path.display());
+    /// Loads the resource `uri` (a `$ref` without its fragment) as seen from `base`.
+    ///
+    /// The ref is joined onto the base URI first; known `$id`s and mapped prefixes are
+    /// looked up offline, and a relative ref falls back to the referring file's directory.
+    pub fn resolve(&mut self, uri: &str, base: &SchemaBase) -> Option<ResolvedRef> {
+        let target = join_uri(&base.uri, uri);
+        if let Some(resolved) = self.resolve_id(&target) {
+            return Some(resolved);
+        }
 
-        let content = if let Some(cached) = self.cache.get(file_path) {
-            cached.clone()
+        let mut candidates = vec![];
+        if is_absolute_uri(&target) {
+            candidates.extend(self.mapped_file(&target));
         } else {
-            let raw = fs::read_to_string(&full_path).ok()?;
+            candidates.push(target.clone());
+        }
+        if !is_absolute_uri(uri) {
+            candidates.push(join_uri(&base.file, uri));
+        }
+
+        for file in candidates {
+            let Some(file) = self.load(&file) else {
+                continue;
+            };
+            let document = self.cache.get(&file)?.clone();
+            let uri = if is_absolute_uri(&target) {
+                target.clone()
+            } else {
+                file.clone()
+            };
+            let uri = declared_base(&document, &uri).unwrap_or(uri);
+            return Some(ResolvedRef {
+                resource: document,
+                base: SchemaBase { uri, file },
+            });
+        }
+
+        log_debug!("⚠️ Unresolved $ref: {} (base {})", uri, base.uri);
+        None
+    }
+
+    fn resolve_id(&self, uri: &str) -> Option<ResolvedRef> {
+        let (file, pointer) = self.ids.get(uri)?;
+        let resource = self.cache.get(file)?.pointer(pointer)?.clone();
+        Some(ResolvedRef {
+            resource,
+            base: SchemaBase {
+                uri: uri.to_string(),
+                file: file.clone(),
+            },
+        })
+    }
+
+    fn mapped_file(&self, uri: &str) -> Option<String> {
+        self.uri_map.iter().find_map(|(prefix, path)| {
+            let rest = uri.strip_prefix(prefix.as_str())?;
+            Some(normalize_uri(&format!(
+                "{}/{}",
+                path.trim_end_matches('/'),
+                rest.trim_start_matches('/')
+            )))
+        })
+    }
+
+    /// Reads `file` (trying a `.json` extension when it has none) and returns its cache key.
+    fn load(&mut self, file: &str) -> Option<String> {
+        let file = normalize_uri(file);
+        let mut names = vec![file.clone()];
+        if Path::new(&file).extension().is_none() {
+            names.push(format!("{}.json", file));
+        }
+
+        for name in names {
+            if self.cache.contains_key(&name) {
+                return Some(name);
+            }
+            let full_path = self.base_path.join(&name);
+            log_debug!("📂 Looking for: {}", full_path.display());
+            let Ok(raw) = fs::read_to_string(&full_path) else {
+                continue;
+            };
             let parsed: Value = serde_json::from_str(&raw).ok()?;
-            self.cache.insert(file_path.to_string(), parsed.clone());
-            parsed
-        };
+            self.index_ids(&name, &parsed, &name, "");
+            self.cache.insert(name.clone(), parsed);
+            return Some(name);
+        }
+        None
+    }
 
-        if pointer.is_empty() {
-            Some(content)
-        } else {
-            content
-                .pointer(&format!(
-                    "/{}",
-                    pointer.trim_start_matches('/').replace("~1", "/")
-                ))
-                .cloned()
+    fn index_ids(&mut self, file: &str, schema: &Value, base_uri: &str, pointer: &str) {
+        match schema {
+            Value::Object(map) => {
+                let base_uri = match declared_base(schema, base_uri) {
+                    Some(uri) => {
+                        self.ids
+                            .entry(uri.clone())
+                            .or_insert_with(|| (file.to_string(), pointer.to_string()));
+                        uri
+                    }
+                    None => base_uri.to_string(),
+                };
+                for (key, value) in map {
+                    // Literal values are data, not schemas, even when they look like one
+                    if matches!(key.as_str(), "enum" | "const" | "default" | "examples") {
+                        continue;
+                    }
+                    let segment = key.replace('~', "~0").replace('/', "~1");
+                    self.index_ids(file, value, &base_uri, &format!("{}/{}", pointer, segment));
+                }
+            }
+            Value::Array(items) => {
+                for (i, value) in items.iter().enumerate() {
+                    self.index_ids(file, value, base_uri, &format!("{}/{}", pointer, i));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The base URI `schema` declares through a (non-fragment) `$id`, resolved against `base_uri`.
+fn declared_base(schema: &Value, base_uri: &str) -> Option<String> {
+    let id = schema.get("$id")?.as_str()?;
+    let id = id.split('#').next().unwrap_or_default();
+    (!id.is_empty()).then(|| join_uri(base_uri, id))
+}
+
+fn is_absolute_uri(uri: &str) -> bool {
+    uri.contains("://") || uri.starts_with("urn:")
+}
+
+/// Splits `scheme://host/path` into `scheme://host` and `/path`; plain paths have no authority.
+fn split_authority(uri: &str) -> (&str, &str) {
+    let Some(i) = uri.find("://") else {
+        return ("", uri);
+    };
+    let end = uri[i + 3..].find('/').map_or(uri.len(), |j| i + 3 + j);
+    uri.split_at(end)
+}
+
+/// Resolves `reference` against `base` (RFC 3986 style, without queries), dropping any fragment:
+/// `shared/gadget/item.json` + `../address.json` → `shared/address.json`.
+fn join_uri(base: &str, reference: &str) -> String {
+    let reference = reference.split('#').next().unwrap_or_default();
+    let base = base.split('#').next().unwrap_or_default();
+    if reference.is_empty() {
+        return base.to_string();
+    }
+    if is_absolute_uri(reference) {
+        return normalize_uri(reference);
+    }
+
+    let (authority, path) = split_authority(base);
+    if let Some(absolute_path) = reference.strip_prefix('/') {
+        return normalize_uri(&format!("{}/{}", authority, absolute_path));
+    }
+    let dir = path.rfind('/').map_or("", |i| &path[..=i]);
+    let dir = if authority.is_empty() {
+        dir
+    } else if dir.is_empty() {
+        "/"
+    } else {
+        dir
+    };
+    normalize_uri(&format!("{}{}{}", authority, dir, reference))
+}
+
+/// Removes `.` and `..` segments from the path part of `uri`.
+fn normalize_uri(uri: &str) -> String {
+    if uri.starts_with("urn:") {
+        return uri.to_string();
+    }
+    let (authority, path) = split_authority(uri);
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                if segments.last().is_some_and(|s| !s.is_empty() && *s != "..") {
+                    segments.pop();
+                } else if authority.is_empty() {
+                    segments.push("..");
+                }
+            }
+            _ => segments.push(segment),
         }
     }
+    format!("{}{}", authority, segments.join("/"))
 }
 
+/// Names the generated files import on their own, besides those recorded by `use_type`.
+const IMPORTED_NAMES: [&str; 4] = ["Deserialize", "Serialize", "Validate", "HashMap"];
+
 pub struct GeneratorContext<'a> {
     pub output: &'a mut Vec<NamedStruct>,
     pub visited: &'a mut HashSet<String>,
-    pub generated_defs: &'a mut HashSet<String>,
+    /// Short type name → `use` line, for every path shortened by `use_type`
+    pub type_imports: &'a mut HashMap<String, String>,
+    pub options: &'a GeneratorOptions,
+    /// Type that `$ref: "#"` points to in the document being generated
+    pub root_name: String,
+    /// Base that relative `$ref`s in the schema being generated resolve against
+    pub base: SchemaBase,
+    /// Types whose generation has started but not finished, outermost first
+    pub in_progress: Vec<String>,
+    /// Rust type generated for each `$ref` already seen, so refs are generated once
+    pub resolved_refs: HashMap<String, String>,
+    /// Schema shape each generated type name was claimed for, see `claim_type_name`
+    pub type_shapes: HashMap<String, Value>,
+    /// `(wanted name, name used, enclosing type)` for every collision that forced a rename
+    pub renames: Vec<(String, String, String)>,
+    /// How to write a value of each generated struct or unit enum as a Rust expression
+    pub literals: HashMap<String, LiteralForm>,
+    /// Generated types that got an `impl Default` (`--with-default`)
+    pub defaults: HashSet<String>,
+    /// Types generated as structs, including ones still in progress
+    pub structs: HashSet<String>,
+    /// First segment of every path passed to `use_type` (`chrono`, `std`, `rust_decimal`)
+    pub crates: BTreeSet<String>,
+}
+
+/// What the schemas of one run share: the types generated into modules other than a
+/// schema's own file (`shared/address.rs`, `x-rust-module`s) and the `$ref`s resolved to
+/// them, so the next schema reuses those types instead of generating them again.
+#[derive(Default)]
+pub struct TypeRegistry {
+    /// Shared types generated so far, written once after the last schema
+    pub shared: Vec<NamedStruct>,
+    /// Crates the generated code names, see `GeneratorContext::crates`
+    pub crates: BTreeSet<String>,
+    resolved_refs: HashMap<String, String>,
+    type_shapes: HashMap<String, Value>,
+    type_imports: HashMap<String, String>,
+    literals: HashMap<String, LiteralForm>,
+    defaults: HashSet<String>,
+}
+
+/// How a value of a generated type is spelled in Rust, used to render `default`/`const`.
+#[derive(Debug, Clone)]
+pub enum LiteralForm {
+    /// Every field of a struct without flattened fields
+    Struct(Vec<LiteralField>),
+    /// `(wire value, variant name)` per unit variant
+    Enum(Vec<(Value, String)>),
+    /// Unit struct standing for exactly this value (an object or array `const`)
+    Unit(Value),
+}
+
+/// One struct field as seen by [`LiteralForm::Struct`].
+#[derive(Debug, Clone)]
+pub struct LiteralField {
+    /// Property name in JSON
+    pub key: String,
+    pub field: String,
+    pub rust_type: String,
+    /// `#[serde(default = "...")]` helper serde calls when the key is missing
+    pub default_fn: Option<String>,
+    /// Whether a value must be supplied, i.e. the key is required and the field isn't an `Option`
+    pub required: bool,
 }
 
 impl<'a> GeneratorContext<'a> {
     pub fn new(
         output: &'a mut Vec<NamedStruct>,
         visited: &'a mut HashSet<String>,
-        generated_defs: &'a mut HashSet<String>,
+        type_imports: &'a mut HashMap<String, String>,
+        options: &'a GeneratorOptions,
+        root_name: &str,
+        base: SchemaBase,
     ) -> Self {
         Self {
             output,
             visited,
-            generated_defs,
+            type_imports,
+            options,
+            root_name: root_name.to_string(),
+            base,
+            in_progress: vec![],
+            resolved_refs: HashMap::new(),
+            type_shapes: HashMap::new(),
+            renames: vec![],
+            literals: HashMap::new(),
+            defaults: HashSet::new(),
+            structs: HashSet::new(),
+            crates: BTreeSet::new(),
         }
     }
-}
 
-pub fn write_named_structs(structs: &[NamedStruct], out_dir: &str, root_name: &str) {
-    let mut root_code = vec![];
-    let mut root_needs_serde = false;
+    /// Picks the type name for `schema`, which would like to be called `name`.
+    ///
+    /// Returns the name and whether that type already exists: a structurally identical
+    /// schema reuses the earlier type (even a renamed one), a different one is qualified
+    /// with the enclosing type (`Address` in `Profile` → `ProfileAddress`) and numbered
+    /// if that is taken too. An `x-rust-name` on the schema replaces `name`.
+    pub fn claim_type_name(&mut self, name: &str, schema: &Value) -> (String, bool) {
+        let name = schema
+            .get("x-rust-name")
+            .and_then(|n| n.as_str())
+            .unwrap_or(name);
+        let shape = type_shape(schema);
+        let reused = std::iter::once(name)
+            .chain(
+                self.renames
+                    .iter()
+                    .filter(|(wanted, _, _)| wanted == name)
+                    .map(|(_, renamed, _)| renamed.as_str()),
+            )
+            .find(|candidate| self.type_shapes.get(*candidate) == Some(&shape));
+        if let Some(reused) = reused {
+            return (reused.to_string(), true);
+        }
 
-    for s in structs {
-        log_debug!("🧾 writing {} → {:?}", s.name, s.output_path);
-        match &s.output_path {
-            Some(path_hint) => {
-                let snake_case_path = to_snake_case(path_hint);
-                let full_path = Path::new(out_dir).join(format!("{}.rs", snake_case_path));
-                let parent = full_path.parent().unwrap();
-                fs::create_dir_all(parent).expect("Failed to create output directory");
-
-                let mut file = fs::File::create(&full_path).expect("Failed to create output file");
-
-                let code = if s.code.contains("Serialize") || s.code.contains("Deserialize") {
-                    let mut prelude = vec!["use serde::{Deserialize, Serialize};".to_string()];
-                    if s.code.contains("Validate") {
-                        prelude.push("use validator::Validate;".to_string());
+        let parent = self.in_progress.last().cloned().unwrap_or_default();
+        let qualified = if name.starts_with(&parent) {
+            name.to_string()
+        } else {
+            format!("{}{}", parent, name)
+        };
+        let candidates = [name.to_string(), qualified.clone()]
+            .into_iter()
+            .chain((2..).map(|i| format!("{}{}", qualified, i)));
+
+        for candidate in candidates {
+            if self.is_imported(&candidate) {
+                continue;
+            }
+            match self.type_shapes.get(&candidate) {
+                Some(existing) if *existing == shape => return (candidate, true),
+                Some(_) => continue,
+                None => {
+                    if candidate != name {
+                        self.renames
+                            .push((name.to_string(), candidate.clone(), parent.clone()));
                     }
-                    format!("{}\n\n{}", prelude.join("\n"), s.code)
+                    self.type_shapes.insert(candidate.clone(), shape);
+                    return (candidate, false);
+                }
+            }
+        }
+        unreachable!("type name candidates are unbounded")
+    }
+
+    /// Wraps `name` in `Box<...>` when that type is still being generated,
+    /// i.e. referencing it by value would make the enclosing type infinitely sized.
+    pub fn box_if_recursive(&self, name: &str) -> String {
+        if self.in_progress.iter().any(|n| n == name) {
+            format!("Box<{}>", name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Rewrites every `a::b::C` path in `rust_type` to `C` and records `use a::b::C;`,
+    /// e.g. `chrono::DateTime<chrono::Utc>` → `DateTime<Utc>`.
+    pub fn use_type(&mut self, rust_type: &str) -> String {
+        let mut short = String::new();
+        let mut token = String::new();
+
+        for ch in rust_type.chars().chain(std::iter::once('\0')) {
+            if ch.is_alphanumeric() || ch == '_' || ch == ':' {
+                token.push(ch);
+                continue;
+            }
+            if let Some((_, name)) = token.rsplit_once("::") {
+                if let Some((root, _)) = token.split_once("::") {
+                    self.crates.insert(root.to_string());
+                }
+                // Keep the full path when a generated type or another import goes by `name`
+                let line = format!("use {};", token);
+                let taken = self.type_shapes.contains_key(name)
+                    || self
+                        .type_imports
+                        .get(name)
+                        .is_some_and(|existing| *existing != line);
+                if taken {
+                    short.push_str(&token);
                 } else {
-                    s.code.clone()
+                    self.type_imports.insert(name.to_string(), line);
+                    short.push_str(name);
+                }
+            } else {
+                short.push_str(&token);
+            }
+            token.clear();
+            if ch != '\0' {
+                short.push(ch);
+            }
+        }
+
+        short
+    }
+
+    /// Whether generated files import something called `name`, which a generated type
+    /// therefore can't be called.
+    fn is_imported(&self, name: &str) -> bool {
+        IMPORTED_NAMES.contains(&name) || self.type_imports.contains_key(name)
+    }
+
+    /// Wraps `item` in the `--set-type` collection for a `uniqueItems` array, adding
+    /// `Eq` plus `Ord` (for `BTreeSet`) or `Hash` (for `HashSet`) to every generated type
+    /// it holds. Returns `None` when some type can't derive them, e.g. `f64` or `serde_json::Value`.
+    pub fn set_type_for(&mut self, item: &str) -> Option<String> {
+        let (collection, derives) = match self.options.set_type {
+            SetType::BTree => (
+                "std::collections::BTreeSet",
+                ["PartialEq", "Eq", "PartialOrd", "Ord"].as_slice(),
+            ),
+            SetType::Hash => (
+                "std::collections::HashSet",
+                ["PartialEq", "Eq", "Hash"].as_slice(),
+            ),
+            SetType::Vec => return None,
+        };
+
+        let mut generated = vec![];
+        if !self.can_derive_set_traits(item, &mut generated) {
+            return None;
+        }
+        for s in self
+            .output
+            .iter_mut()
+            .filter(|s| generated.contains(&s.name))
+        {
+            s.code = add_derives(&s.code, derives);
+        }
+        Some(self.use_type(&format!("{}<{}>", collection, item)))
+    }
+
+    /// Whether `rust_type` can be a set element under `--set-type`, collecting the
+    /// generated types that need the derives into `generated`.
+    fn can_derive_set_traits(&self, rust_type: &str, generated: &mut Vec<String>) -> bool {
+        let (head, args) = split_generic(rust_type);
+        let args_ok = |ctx: &Self, generated: &mut Vec<String>| {
+            args.iter()
+                .all(|arg| ctx.can_derive_set_traits(arg, generated))
+        };
+        match head {
+            "" | "Option" | "Vec" | "Box" | "BTreeSet" | "BTreeMap" => args_ok(self, generated),
+            "HashMap" | "HashSet" => false,
+            "String" | "bool" | "char" | "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32"
+            | "u64" | "Uuid" | "DateTime" | "Utc" | "NaiveDate" | "NaiveTime" | "Url"
+            | "IpAddr" => args_ok(self, generated),
+            name => {
+                if generated.iter().any(|g| g == name) {
+                    return true;
+                }
+                let Some(item) = self.output.iter().find(|s| s.name == name) else {
+                    return false;
                 };
+                generated.push(name.to_string());
+                item.members
+                    .iter()
+                    .all(|member| self.can_derive_set_traits(member, generated))
+            }
+        }
+    }
+}
+
+/// Splits `Outer<A, B>` into `("Outer", ["A", "B"])` and `(A, B)` into `("", ["A", "B"])`.
+fn split_generic(rust_type: &str) -> (&str, Vec<&str>) {
+    let (head, inner) = if let Some(inner) = rust_type
+        .strip_prefix('(')
+        .and_then(|t| t.strip_suffix(')'))
+    {
+        ("", inner)
+    } else if let Some((head, rest)) = rust_type.split_once('<') {
+        (head, rest.strip_suffix('>').unwrap_or(rest))
+    } else {
+        return (rust_type, vec![]);
+    };
 
-                file.write_all(code.as_bytes()).expect("Write failed");
-                log_debug!("✅ Generated: {}", full_path.display());
+    let mut args = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, ch) in inner.char_indices() {
+        match ch {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[start..i].trim());
+                start = i + 1;
             }
-            None => {
-                if s.code.contains("Serialize") || s.code.contains("Deserialize") {
-                    root_needs_serde = true;
-                }
-                root_code.push(s.code.as_str());
+            _ => {}
+        }
+    }
+    args.push(inner[start..].trim());
+    args.retain(|arg| !arg.is_empty());
+    (head, args)
+}
+
+/// Appends `derives` missing from the first `#[derive(...)]` in `code`.
+fn add_derives(code: &str, derives: &[&str]) -> String {
+    let Some(start) = code.find("#[derive(").map(|i| i + "#[derive(".len()) else {
+        return code.to_string();
+    };
+    let Some(len) = code[start..].find(")]") else {
+        return code.to_string();
+    };
+
+    let mut traits = code[start..start + len].split(", ").collect::<Vec<_>>();
+    for derive in derives {
+        if !traits.contains(derive) {
+            traits.push(derive);
+        }
+    }
+    format!(
+        "{}{}{}",
+        &code[..start],
+        traits.join(", "),
+        &code[start + len..]
+    )
+}
+
+/// Types of the `pub name: Type,` fields among generated struct lines.
+fn field_types(fields: &[String]) -> Vec<String> {
+    fields
+        .iter()
+        .flat_map(|field| field.lines())
+        .filter_map(|line| line.trim().strip_prefix("pub "))
+        .filter_map(|line| line.split_once(": "))
+        .map(|(_, rust_type)| rust_type.trim_end_matches(',').to_string())
+        .collect()
+}
+
+/// Whether `code` mentions `name` as a whole identifier.
+fn mentions_type(code: &str, name: &str) -> bool {
+    code.match_indices(name).any(|(i, _)| {
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == ':';
+        let before = code[..i].chars().next_back();
+        let after = code[i + name.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+/// `use` line importing `name` from the generated file `to` into the generated file
+/// `from`, both given as paths relative to the out dir (`shared/address`).
+fn import_line(options: &GeneratorOptions, from: &str, to: &str, name: &str) -> String {
+    let prefix = if options.relative_imports {
+        "super::".repeat(from.split('/').count())
+    } else {
+        format!("{}::", options.module_path)
+    };
+    format!(
+        "use {}{}::{};",
+        prefix,
+        to_snake_case(to).replace('/', "::"),
+        name
+    )
+}
+
+/// Turns an `x-rust-module` (`models::user` or `models/user`) into an output path.
+fn module_path(module: &str) -> String {
+    module
+        .replace("::", "/")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(to_snake_case)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The parts of `schema` that decide the generated type; annotations such as
+/// `description` don't, so two schemas differing only in docs share one type.
+fn type_shape(schema: &Value) -> Value {
+    match schema {
+        Value::Object(map) => map
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "description" | "examples" | "$comment"))
+            .map(|(key, value)| {
+                let value = match (key.as_str(), value) {
+                    // Keys of these maps are names, not keywords, so keep them all
+                    (
+                        "properties" | "patternProperties" | "$defs" | "definitions",
+                        Value::Object(named),
+                    ) => named
+                        .iter()
+                        .map(|(name, schema)| (name.clone(), type_shape(schema)))
+                        .collect(),
+                    _ => type_shape(value),
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        Value::Array(items) => items.iter().map(type_shape).collect(),
+        other => other.clone(),
+    }
+}
+
+/// Writes the items that have an output path, one file per path.
+pub fn write_module_files(structs: &[NamedStruct], out_dir: &str) {
+    // Items sharing an output path end up in one file, in generation order
+    let mut files: Vec<(&str, Vec<&NamedStruct>)> = vec![];
+
+    for s in structs {
+        log_debug!("🧾 writing {} → {:?}", s.name, s.output_path);
+        if let Some(path_hint) = &s.output_path {
+            match files.iter_mut().find(|(path, _)| path == path_hint) {
+                Some((_, items)) => items.push(s),
+                None => files.push((path_hint, vec![s])),
+            }
+        }
+    }
+
+    for (path_hint, items) in files {
+        let snake_case_path = to_snake_case(path_hint);
+        let full_path = Path::new(out_dir).join(format!("{}.rs", snake_case_path));
+        let parent = full_path.parent().unwrap();
+        fs::create_dir_all(parent).expect("Failed to create output directory");
+
+        let mut file = fs::File::create(&full_path).expect("Failed to create output file");
+
+        let mut prelude = vec![];
+        if items
+            .iter()
+            .any(|s| s.code.contains("Serialize") || s.code.contains("Deserialize"))
+        {
+            prelude.push("use serde::{Deserialize, Serialize};".to_string());
+            if items.iter().any(|s| s.code.contains("Validate")) {
+                prelude.push("use validator::Validate;".to_string());
             }
         }
+        let mut imports: Vec<String> = items.iter().flat_map(|s| s.imports.clone()).collect();
+        imports.sort();
+        imports.dedup();
+        prelude.extend(imports);
+
+        let body = items
+            .iter()
+            .map(|s| s.code.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let code = if prelude.is_empty() {
+            body
+        } else {
+            format!("{}\n\n{}", prelude.join("\n"), body)
+        };
+
+        file.write_all(code.as_bytes()).expect("Write failed");
+        log_debug!("✅ Generated: {}", full_path.display());
     }
+}
+
+/// Writes the items without an output path to `{root_name}.rs`.
+pub fn write_root_file(structs: &[NamedStruct], out_dir: &str, root_name: &str) {
+    let root = structs
+        .iter()
+        .filter(|s| s.output_path.is_none())
+        .collect::<Vec<_>>();
+    let root_needs_serde = root
+        .iter()
+        .any(|s| s.code.contains("Serialize") || s.code.contains("Deserialize"));
+    let root_needs_validate = root.iter().any(|s| s.code.contains("Validate"));
+    let root_code = root.iter().map(|s| s.code.as_str()).collect::<Vec<_>>();
 
     let full_path = Path::new(out_dir).join(format!("{}.rs", to_snake_case(root_name)));
     let parent = full_path.parent().unwrap();
@@ -126,7 +792,11 @@ pub fn write_named_structs(structs: &[NamedStruct], out_dir: &str, root_name: &s
 
     let mut joined = String::new();
     if root_needs_serde {
-        joined.push_str("use serde::{Deserialize, Serialize};\n\n");
+        joined.push_str("use serde::{Deserialize, Serialize};\n");
+        if root_needs_validate {
+            joined.push_str("use validator::Validate;\n");
+        }
+        joined.push('\n');
     }
     joined.push_str(&root_code.join("\n\n"));
 
@@ -134,12 +804,10 @@ pub fn write_named_structs(structs: &[NamedStruct], out_dir: &str, root_name: &s
         .expect("Root write failed");
 
     log_debug!("✅ Stub generated: {}", full_path.display());
-
-    // 🔧 NEW: Generate mod.rs files for all subfolders
-    generate_mod_rs_recursively(Path::new(out_dir)).expect("Failed to generate mod.rs files");
 }
 
-fn generate_mod_rs_recursively(dir: &Path) -> std::io::Result<()> {
+/// Writes a `mod.rs` declaring the files and subfolders of `dir` and of each subfolder.
+pub fn generate_mod_rs_recursively(dir: &Path) -> std::io::Result<()> {
     if !dir.is_dir() {
         return Ok(());
     }
@@ -157,12 +825,12 @@ fn generate_mod_rs_recursively(dir: &Path) -> std::io::Result<()> {
                     path.file_name().unwrap().to_str().unwrap()
                 ));
             }
-        } else if path.is_file() {
-            if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
-                if name != "mod" && path.extension().map_or(false, |e| e == "rs") {
-                    mod_lines.push(format!("pub mod {};", name));
-                }
-            }
+        } else if path.is_file()
+            && let Some(name) = path.file_stem().and_then(|n| n.to_str())
+            && name != "mod"
+            && path.extension().is_some_and(|e| e == "rs")
+        {
+            mod_lines.push(format!("pub mod {};", name));
         }
     }
 
@@ -180,50 +848,108 @@ fn generate_mod_rs_recursively(dir: &Path) -> std::io::Result<()> {
 pub fn generate_rust_structs_from_schema(
     root_name: &str,
     schema: &Value,
+    base: SchemaBase,
     resolver: &mut RefResolver,
     with_docs: &bool,
-) -> Vec<NamedStruct> {
-    let mut structs = vec![];
-    let mut visited = HashSet::new();
-    let mut generated_defs = HashSet::new();
-    let definitions = schema.get("definitions").cloned().unwrap_or(Value::Null);
-
-    let mut ctx = GeneratorContext::new(&mut structs, &mut visited, &mut generated_defs);
-
-    // if let Some(def_map) = definitions.as_object() {
-    //     for (name, def_schema) in def_map {
-    //         if !ctx.generated_defs.contains(name) {
-    //             extract_struct_recursive(
-    //                 &to_pascal_case(name),
-    //                 def_schema,
-    //                 &mut ctx,
-    //                 format!("#/definitions/{}", name),
-    //                 &Value::Null,
-    //                 resolver,
-    //                 Some(name.clone()),
-    //                 *with_docs,
-    //             );
-    //         }
-    //     }
-    // }
+    options: &GeneratorOptions,
+    registry: &mut TypeRegistry,
+) -> Result<Vec<NamedStruct>, Vec<String>> {
+    let root_uri = base.uri.clone();
+    let root_path = to_snake_case(base.file.strip_suffix(".json").unwrap_or(&base.file));
+    let mut structs = std::mem::take(&mut registry.shared);
+    let mut visited: HashSet<String> = structs.iter().map(|s| s.name.clone()).collect();
+    let mut type_imports = registry.type_imports.clone();
 
-    extract_struct_recursive(
+    let mut ctx = GeneratorContext::new(
+        &mut structs,
+        &mut visited,
+        &mut type_imports,
+        options,
         root_name,
+        base,
+    );
+    ctx.resolved_refs = registry.resolved_refs.clone();
+    ctx.type_shapes = registry.type_shapes.clone();
+    ctx.literals = registry.literals.clone();
+    ctx.defaults = registry.defaults.clone();
+    ctx.crates = registry.crates.clone();
+
+    let (root_type, _) = ctx.claim_type_name(root_name, schema);
+    ctx.root_name = root_type.clone();
+
+    extract_struct_recursive(
+        &root_type,
         schema,
         &mut ctx,
         "#".to_string(),
-        &definitions,
+        schema,
         resolver,
         None,
         *with_docs,
     );
 
+    apply_custom_derives(&mut ctx)?;
+
+    // ✅ x-rust-module: the type goes to that module instead of the file it came from
+    for s in &mut *ctx.output {
+        if let Some(module) = ctx
+            .type_shapes
+            .get(&s.name)
+            .and_then(|shape| shape.get("x-rust-module"))
+            .and_then(|m| m.as_str())
+        {
+            s.output_path = Some(module_path(module));
+        }
+    }
+
     let mut use_lines = vec![];
     for s in &mut *ctx.output {
         let is_root_file = s.output_path.is_none() || s.output_path.as_deref() == Some(root_name);
-        if (s.output_path.is_none() || is_root_file) && s.code.contains("HashMap<") {
-            use_lines.push("use std::collections::HashMap;".to_string());
+        if s.code.contains("HashMap<") {
+            let line = "use std::collections::HashMap;".to_string();
+            if is_root_file {
+                use_lines.push(line);
+            } else {
+                s.imports.push(line);
+            }
+        }
+        for (name, line) in ctx.type_imports.iter() {
+            if !mentions_type(&s.code, name) {
+                continue;
+            }
+            if is_root_file {
+                use_lines.push(line.clone());
+            } else {
+                s.imports.push(line.clone());
+            }
+        }
+        s.imports.sort();
+    }
+
+    // Types generated into other modules are imported by the non-root files that use them;
+    // so are root types, which an `x-rust-module` type may have left behind
+    let module_types: Vec<(String, String)> = ctx
+        .output
+        .iter()
+        .map(
+            |s| match s.output_path.as_ref().filter(|path| *path != root_name) {
+                Some(path) => (s.name.clone(), path.clone()),
+                None => (s.name.clone(), root_path.clone()),
+            },
+        )
+        .collect();
+    for s in &mut *ctx.output {
+        let Some(own_path) = s.output_path.clone().filter(|path| path != root_name) else {
+            continue;
+        };
+        for (name, path) in &module_types {
+            if *path != own_path && mentions_type(&s.code, name) {
+                s.imports
+                    .push(import_line(ctx.options, &own_path, path, name));
+            }
         }
+        s.imports.sort();
+        s.imports.dedup();
     }
 
     use_lines.sort();
@@ -231,29 +957,41 @@ pub fn generate_rust_structs_from_schema(
 
     let mut import_uses = vec![];
     for s in &mut *ctx.output {
-        if let Some(ref path) = s.output_path {
-            if path != root_name {
-                let mod_path = format!("generated::{}", path.replace('/', "::"));
-                import_uses.push(format!("use crate::{}::{};", mod_path, s.name));
-            }
+        if let Some(ref path) = s.output_path
+            && path != root_name
+        {
+            import_uses.push(import_line(ctx.options, &root_path, path, &s.name));
         }
     }
 
+    // Only what the root file mentions: shared types from earlier schemas are in the output too
+    let root_code = ctx
+        .output
+        .iter()
+        .filter(|s| s.output_path.is_none() || s.output_path.as_deref() == Some(root_name))
+        .map(|s| s.code.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    import_uses.retain(|line| {
+        let name = line
+            .trim_end_matches(';')
+            .rsplit("::")
+            .next()
+            .unwrap_or_default();
+        mentions_type(&root_code, name)
+    });
     import_uses.sort();
     import_uses.dedup();
 
-    for (i, line) in import_uses
-        .into_iter()
-        .chain(use_lines.into_iter())
-        .rev()
-        .enumerate()
-    {
+    for (i, line) in import_uses.into_iter().chain(use_lines).rev().enumerate() {
         ctx.output.insert(
             0,
             NamedStruct {
                 name: format!("__use_{}", i),
                 code: line,
                 output_path: None,
+                imports: vec![],
+                members: vec![],
             },
         );
     }
@@ -262,54 +1000,383 @@ pub fn generate_rust_structs_from_schema(
         log_debug!("🧾 {} → {:?}", s.name, s.output_path);
     }
 
-    ctx.output.to_vec()
-}
+    if !ctx.renames.is_empty() {
+        log_warn!("🔀 Renamed {} colliding type name(s):", ctx.renames.len());
+        for (wanted, renamed, parent) in &ctx.renames {
+            log_warn!("   {} → {} (in {})", wanted, renamed, parent);
+        }
+    }
 
-fn reserved_keywords() -> HashSet<&'static str> {
-    [
-        // Strict keywords
-        "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
-        "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
-        "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
-        "where", "while", // Reserved for future use
-        "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
-        "unsized", "virtual", "yield", "try", // Edition-specific
-        "async", "await", "dyn", // Some internal / contextual identifiers
-        "union",
-    ]
-    .into_iter()
-    .collect()
-}
+    let shared = ctx
+        .output
+        .iter()
+        .filter(|s| s.output_path.as_ref().is_some_and(|path| path != root_name))
+        .cloned()
+        .collect::<Vec<_>>();
+    let is_shared = |name: &String| shared.iter().any(|s| &s.name == name);
+    registry.resolved_refs = ctx
+        .resolved_refs
+        .iter()
+        .filter(|(key, _)| !key.starts_with(&format!("{}#", root_uri)))
+        .map(|(key, rust_type)| (key.clone(), rust_type.clone()))
+        .collect();
+    registry.type_shapes = ctx
+        .type_shapes
+        .iter()
+        .filter(|(name, _)| is_shared(name))
+        .map(|(name, shape)| (name.clone(), shape.clone()))
+        .collect();
+    registry.literals = ctx
+        .literals
+        .iter()
+        .filter(|(name, _)| is_shared(name))
+        .map(|(name, form)| (name.clone(), form.clone()))
+        .collect();
+    registry.defaults = ctx
+        .defaults
+        .iter()
+        .filter(|name| is_shared(name))
+        .cloned()
+        .collect();
+    registry.type_imports = ctx.type_imports.clone();
+    registry.crates = ctx.crates.clone();
+    registry.shared = shared;
 
-fn sanitize_field_name(original: &str) -> (String, Option<String>) {
-    let reserved_keywords = reserved_keywords();
+    Ok(ctx.output.to_vec())
+}
 
-    // Case: starts with $
-    if original.starts_with('$') {
-        let base = &original[1..];
-        let field_name = if reserved_keywords.contains(base) {
-            format!("r#{}", base) // หรือ base.to_owned() + "_" ถ้าไม่อยากใช้ raw identifiers
-        } else {
-            to_snake_case(base)
+/// Adds `--derive` and each type's `x-rust-derive` (plus the traits they build on, e.g.
+/// `PartialEq` for `Eq`) to the generated types, and inserts its `x-rust-attributes`.
+///
+/// A derive that can't compile for some field type is refused, naming the field.
+fn apply_custom_derives(ctx: &mut GeneratorContext) -> Result<(), Vec<String>> {
+    let mut requested = HashMap::new();
+    let mut derives = HashMap::new();
+    for s in ctx.output.iter() {
+        let Some(mut existing) = derive_list(&s.code) else {
+            continue;
         };
-        return (field_name, Some(original.to_string()));
-    }
+        // `--with-default` already wrote an `impl Default` by hand
+        if ctx.defaults.contains(&s.name) && !existing.iter().any(|d| d == "Default") {
+            existing.push("Default".to_string());
+        }
+        let schema = ctx.type_shapes.get(&s.name);
+        let mut wanted = ctx.options.derives.clone();
+        wanted.extend(schema_list(schema, "x-rust-derive"));
+        let wanted = with_supertraits(&wanted)
+            .into_iter()
+            .filter(|d| !existing.contains(d))
+            .collect::<Vec<_>>();
 
-    // Case: regular keyword
-    if reserved_keywords.contains(original) {
-        return (format!("r#{}", original), Some(original.to_string()));
+        let mut all = existing;
+        all.extend(wanted.iter().cloned());
+        derives.insert(s.name.clone(), all);
+        requested.insert(s.name.clone(), wanted);
     }
 
-    // Normal case
-    (to_snake_case(original), None)
-}
-
+    let mut errors = vec![];
+    for s in ctx.output.iter() {
+        let Some(wanted) = requested.get(&s.name) else {
+            continue;
+        };
+        for derive in wanted {
+            if derive == "Default" && s.code.contains(&format!("pub enum {} ", s.name)) {
+                errors.push(format!(
+                    "❌ `{}` can't derive `Default`: it's an enum",
+                    s.name
+                ));
+                continue;
+            }
+            for member in &s.members {
+                if let Some(culprit) = lacks_trait(member, derive, &derives) {
+                    errors.push(format!(
+                        "❌ `{}` can't derive `{}`: {} holds `{}`, which doesn't implement it",
+                        s.name,
+                        derive,
+                        member_label(&s.code, member),
+                        culprit
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    for s in ctx.output.iter_mut() {
+        let Some(wanted) = requested.get(&s.name) else {
+            continue;
+        };
+        let wanted = wanted.iter().map(|d| d.as_str()).collect::<Vec<_>>();
+        s.code = add_derives(&s.code, &wanted);
+
+        let attributes = schema_list(ctx.type_shapes.get(&s.name), "x-rust-attributes")
+            .into_iter()
+            .map(|a| {
+                if a.starts_with("#[") {
+                    a
+                } else {
+                    format!("#[{}]", a)
+                }
+            })
+            .collect::<Vec<_>>();
+        // A shared type comes back for every schema of a run, its attributes already added
+        let attributes = attributes
+            .into_iter()
+            .filter(|a| !s.code.contains(a.as_str()))
+            .collect::<Vec<_>>();
+        if let Some(start) = s.code.find("#[derive(")
+            && let Some(end) = s.code[start..].find('\n')
+            && !attributes.is_empty()
+        {
+            let at = start + end + 1;
+            s.code
+                .insert_str(at, &format!("{}\n", attributes.join("\n")));
+        }
+    }
+    Ok(())
+}
+
+/// Traits listed in the first `#[derive(...)]` of `code`.
+fn derive_list(code: &str) -> Option<Vec<String>> {
+    let start = code.find("#[derive(")? + "#[derive(".len();
+    let len = code[start..].find(")]")?;
+    Some(
+        code[start..start + len]
+            .split(',')
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty())
+            .collect(),
+    )
+}
+
+/// A vendor extension holding either a list of strings or one comma-separated string.
+fn schema_list(schema: Option<&Value>, key: &str) -> Vec<String> {
+    match schema.and_then(|s| s.get(key)) {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|i| i.as_str())
+            .map(|i| i.trim().to_string())
+            .collect(),
+        Some(Value::String(items)) if key == "x-rust-derive" => items
+            .split(',')
+            .map(|i| i.trim().to_string())
+            .filter(|i| !i.is_empty())
+            .collect(),
+        Some(Value::String(item)) => vec![item.trim().to_string()],
+        _ => vec![],
+    }
+}
+
+/// `derives` with the std traits they require listed first (`Eq` → `PartialEq, Eq`).
+fn with_supertraits(derives: &[String]) -> Vec<String> {
+    fn push(derive: &str, out: &mut Vec<String>) {
+        let required: &[&str] = match derive {
+            "Copy" => &["Clone"],
+            "Eq" | "PartialOrd" => &["PartialEq"],
+            "Ord" => &["PartialOrd", "Eq"],
+            _ => &[],
+        };
+        for r in required {
+            push(r, out);
+        }
+        if !out.iter().any(|d| d == derive) {
+            out.push(derive.to_string());
+        }
+    }
+
+    let mut out = vec![];
+    for derive in derives {
+        push(derive, &mut out);
+    }
+    out
+}
+
+/// The part of `rust_type` that doesn't implement `derive`, if any. Generated types are
+/// looked up in `derives`; traits and types this table doesn't know are assumed fine.
+fn lacks_trait(
+    rust_type: &str,
+    derive: &str,
+    derives: &HashMap<String, Vec<String>>,
+) -> Option<String> {
+    const KNOWN: [&str; 8] = [
+        "Clone",
+        "Copy",
+        "PartialEq",
+        "Eq",
+        "PartialOrd",
+        "Ord",
+        "Hash",
+        "Default",
+    ];
+    if !KNOWN.contains(&derive) {
+        return None;
+    }
+
+    let (head, args) = split_generic(rust_type);
+    let unsupported: &[&str] = match head {
+        "Option" | "" => &[],
+        "Box" | "Vec" | "String" | "BTreeMap" | "BTreeSet" => &["Copy"],
+        "HashMap" | "HashSet" => &["Copy", "Hash", "PartialOrd", "Ord"],
+        "f64" => &["Eq", "Ord", "Hash"],
+        "serde_json::Value" => &["Copy", "PartialOrd", "Ord", "Hash"],
+        "Url" => &["Copy", "Default"],
+        "IpAddr" => &["Default"],
+        "bool" | "Uuid" | "DateTime" | "Utc" | "NaiveDate" | "NaiveTime" => &[],
+        int if is_integer_type(int) => &[],
+        name => {
+            return match derives.get(name) {
+                Some(list) if !list.iter().any(|d| d == derive) => Some(name.to_string()),
+                _ => None,
+            };
+        }
+    };
+    if unsupported.contains(&derive) {
+        return Some(rust_type.to_string());
+    }
+
+    // containers are `Default` whatever they hold
+    let needs_args = derive != "Default" || matches!(head, "" | "Box");
+    if !needs_args {
+        return None;
+    }
+    args.iter()
+        .find_map(|arg| lacks_trait(arg, derive, derives))
+}
+
+/// How to point at the field or variant of type `member` in a generated item's `code`.
+fn member_label(code: &str, member: &str) -> String {
+    for line in code.lines().map(str::trim) {
+        if let Some((field, rust_type)) = line.strip_prefix("pub ").and_then(|l| l.split_once(": "))
+            && rust_type.trim_end_matches(',') == member
+        {
+            return format!("field `{}`", field);
+        }
+        if let Some(variant) = line
+            .strip_suffix(&format!("({}),", member))
+            .filter(|v| v.chars().all(|c| c.is_alphanumeric() || c == '_'))
+        {
+            return format!("variant `{}`", variant);
+        }
+    }
+    format!("`{}`", member)
+}
+
+fn reserved_keywords() -> HashSet<&'static str> {
+    [
+        // Strict keywords
+        "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+        "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+        "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+        "where", "while", // Reserved for future use
+        "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+        "unsized", "virtual", "yield", "try", // Edition-specific
+        "async", "await", "dyn", // Some internal / contextual identifiers
+        "union",
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn sanitize_field_name(original: &str) -> (String, Option<String>) {
+    let reserved_keywords = reserved_keywords();
+
+    // Case: starts with $
+    if let Some(base) = original.strip_prefix('$') {
+        let field_name = if reserved_keywords.contains(base) {
+            format!("r#{}", base) // หรือ base.to_owned() + "_" ถ้าไม่อยากใช้ raw identifiers
+        } else {
+            snake_case_field(base)
+        };
+        return (field_name, Some(original.to_string()));
+    }
+
+    // Case: regular keyword
+    if reserved_keywords.contains(original) {
+        return (format!("r#{}", original), Some(original.to_string()));
+    }
+
+    // Normal case
+    (snake_case_field(original), None)
+}
+
+/// `to_snake_case` without the doubled or dangling `_`s it leaves around separators,
+/// e.g. `X-Request-Id` → `x_request_id` rather than `x__request__id`.
+fn snake_case_field(key: &str) -> String {
+    let snake = to_snake_case(key);
+    let field = snake
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if field.is_empty() { snake } else { field }
+}
+
+/// Serde `rename_all` rules we can emit, tried in order.
+const RENAME_ALL_RULES: [&str; 3] = ["camelCase", "PascalCase", "kebab-case"];
+
+/// Applies a serde `rename_all` rule to a field name, mirroring `serde_derive`.
+fn apply_rename_rule(rule: Option<&str>, field_name: &str) -> String {
+    let field = field_name.trim_start_matches("r#");
+    let pascal = || {
+        let mut out = String::new();
+        let mut capitalize = true;
+        for ch in field.chars() {
+            if ch == '_' {
+                capitalize = true;
+            } else if capitalize {
+                out.push(ch.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                out.push(ch);
+            }
+        }
+        out
+    };
+
+    match rule {
+        Some("camelCase") => {
+            let pascal = pascal();
+            match pascal.chars().next() {
+                Some(first) => first.to_ascii_lowercase().to_string() + &pascal[first.len_utf8()..],
+                None => pascal,
+            }
+        }
+        Some("PascalCase") => pascal(),
+        Some("kebab-case") => field.replace('_', "-"),
+        _ => field.to_string(),
+    }
+}
+
+/// Picks a struct-level `rename_all` rule when every plain property key follows it.
+///
+/// Keys that already need an explicit rename (`$`-prefixed or reserved) are ignored,
+/// and no rule is picked when the keys are plain snake_case already.
+fn infer_rename_all<'k>(keys: impl Iterator<Item = &'k String>) -> Option<&'static str> {
+    let plain = keys
+        .filter(|key| sanitize_field_name(key).1.is_none())
+        .collect::<Vec<_>>();
+
+    if plain.iter().all(|key| to_snake_case(key) == **key) {
+        return None;
+    }
+
+    RENAME_ALL_RULES.into_iter().find(|rule| {
+        plain
+            .iter()
+            .all(|key| apply_rename_rule(Some(rule), &to_snake_case(key)) == **key)
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn extract_struct_recursive(
     name: &str,
     schema: &Value,
     ctx: &mut GeneratorContext,
     _path: String,
-    definitions: &Value,
+    document: &Value,
     resolver: &mut RefResolver,
     output_path: Option<String>,
     with_docs: bool,
@@ -320,8 +1387,28 @@ pub fn extract_struct_recursive(
     }
     ctx.visited.insert(name.to_string());
 
+    // ✅ if/then/else keyed on a sibling field → tagged enum with one struct per shape
+    if let Some((tag, variants)) = conditional_variants(schema) {
+        emit_conditional_enum(
+            name,
+            &tag,
+            variants,
+            ctx,
+            document,
+            resolver,
+            output_path,
+            with_docs,
+        );
+        return;
+    }
+    let schema = &with_conditional_properties(schema);
+    ctx.in_progress.push(name.to_string());
+    ctx.structs.insert(name.to_string());
+
     let mut fields = vec![];
     let mut extra_helpers = vec![];
+    let mut literal_fields = vec![];
+    let mut rename_all = None;
 
     // ✅ properties
     if let Some(properties) = schema.get("properties") {
@@ -335,8 +1422,14 @@ pub fn extract_struct_recursive(
             })
             .unwrap_or_default();
 
-        for (key, prop) in properties.as_object().unwrap() {
-            let (field_name, rename_attr) = sanitize_field_name(key);
+        let properties = properties.as_object().unwrap();
+        rename_all = infer_rename_all(properties.keys());
+
+        for (key, prop) in properties {
+            let (field_name, mut rename_attr) = sanitize_field_name(key);
+            if rename_attr.is_none() && apply_rename_rule(rename_all, &field_name) != *key {
+                rename_attr = Some(key.to_string());
+            }
             if let Some(rename) = rename_attr {
                 fields.push(format!("    #[serde(rename = \"{}\")]", rename));
             }
@@ -346,14 +1439,14 @@ pub fn extract_struct_recursive(
                 prop,
                 key,
                 ctx,
-                definitions,
+                document,
                 resolver,
                 output_path.clone(),
                 with_docs,
             )
             .unwrap_or_else(|| "serde_json::Value".to_string());
 
-            let final_type = if is_required {
+            let final_type = if is_required || rust_type.starts_with("Option<") {
                 rust_type.clone()
             } else {
                 format!("Option<{}>", rust_type)
@@ -366,43 +1459,156 @@ pub fn extract_struct_recursive(
                 }
             }
 
-            if let Some(default_fn) = generate_default_function(key, prop) {
+            let mut field_default = None;
+            let helper_name = field_name.trim_start_matches("r#");
+            if let Some(default_fn) = generate_default_function(name, helper_name, prop)
+                && let Some(helper) =
+                    default_fn_code_string(&default_fn, key, prop, &final_type, ctx)
+            {
                 fields.push(format!("    #[serde(default = \"{}\")]", default_fn));
-                extra_helpers.push(default_fn_code_string(key, prop));
+                extra_helpers.push(helper);
+                field_default = Some(default_fn);
+            }
+
+            if ctx.options.with_validation {
+                let (validate_attr, regex_static) =
+                    validation_attr(name, &field_name, prop, &rust_type, ctx);
+                if let Some(attr) = validate_attr {
+                    fields.push(attr);
+                }
+                if let Some(helper) = regex_static {
+                    extra_helpers.push(helper);
+                }
             }
 
             fields.push(format!("    pub {}: {},", field_name, final_type));
+            literal_fields.push(LiteralField {
+                key: key.to_string(),
+                field: field_name,
+                required: !final_type.starts_with("Option<"),
+                rust_type: final_type,
+                default_fn: field_default,
+            });
         }
     }
 
     // ✅ patternProperties
-    if let Some(patterns) = schema.get("patternProperties") {
-        if let Some(pattern_map) = patterns.as_object() {
-            for (i, (pattern, pat_schema)) in pattern_map.iter().enumerate() {
-                let field_name = format!("pattern_{}", i + 1);
-                let rust_type = infer_rust_type(
-                    pat_schema,
-                    &field_name,
-                    ctx,
-                    definitions,
-                    resolver,
-                    output_path.clone(),
-                    with_docs,
-                )
-                .unwrap_or_else(|| "serde_json::Value".to_string());
+    if let Some(patterns) = schema.get("patternProperties")
+        && let Some(pattern_map) = patterns.as_object()
+    {
+        for (i, (pattern, pat_schema)) in pattern_map.iter().enumerate() {
+            let field_name = format!("pattern_{}", i + 1);
+            let rust_type = infer_rust_type(
+                pat_schema,
+                &field_name,
+                ctx,
+                document,
+                resolver,
+                output_path.clone(),
+                with_docs,
+            )
+            .map(unbox)
+            .unwrap_or_else(|| "serde_json::Value".to_string());
 
-                let doc = if with_docs {
-                    format!("    /// Keys matching pattern: `{}`\n", pattern)
-                } else {
-                    "".to_string()
-                };
+            let doc = if with_docs {
+                format!("    /// Keys matching pattern: `{}`\n", pattern)
+            } else {
+                "".to_string()
+            };
 
-                fields.push(format!(
-                    "{}    #[serde(flatten)]\n    pub {}: Option<HashMap<String, {}>>,",
-                    doc, field_name, rust_type
-                ));
+            fields.push(format!(
+                "{}    #[serde(flatten)]\n    pub {}: Option<HashMap<String, {}>>,",
+                doc, field_name, rust_type
+            ));
+            literal_fields.push(LiteralField {
+                key: field_name.clone(),
+                field: field_name,
+                rust_type: format!("Option<HashMap<String, {}>>", rust_type),
+                default_fn: None,
+                required: false,
+            });
+        }
+    }
+
+    // ✅ additionalProperties schema → flattened map of every other key
+    if let Some(ap) = schema
+        .get("additionalProperties")
+        .filter(|ap| ap.is_object())
+    {
+        let taken = schema.pointer("/properties/extra").is_some();
+        let field_name = if taken {
+            "additional_properties"
+        } else {
+            "extra"
+        };
+        let rust_type = infer_rust_type(
+            ap,
+            &format!("{}Value", name),
+            ctx,
+            document,
+            resolver,
+            output_path.clone(),
+            with_docs,
+        )
+        .map(unbox)
+        .unwrap_or_else(|| "serde_json::Value".to_string());
+
+        if with_docs {
+            let doc_block = doc_lines_to_string_block(ap, 4);
+            if !doc_block.is_empty() {
+                fields.push(doc_block);
             }
         }
+        fields.push(format!(
+            "    #[serde(flatten)]\n    pub {}: HashMap<String, {}>,",
+            field_name, rust_type
+        ));
+        literal_fields.push(LiteralField {
+            key: field_name.to_string(),
+            field: field_name.to_string(),
+            rust_type: format!("HashMap<String, {}>", rust_type),
+            default_fn: None,
+            required: false,
+        });
+    }
+
+    // serde can't combine `deny_unknown_fields` with flattened fields
+    let closed = ["additionalProperties", "unevaluatedProperties"]
+        .iter()
+        .any(|keyword| schema.get(keyword) == Some(&Value::Bool(false)));
+    let flattened = fields.iter().any(|f| f.contains("#[serde(flatten)]"));
+    let deny_unknown_fields = closed && !flattened;
+
+    // ✅ impl Default (derived when no field has a schema default) / builder
+    let mut derive_default = false;
+    if ctx.options.with_default {
+        if literal_fields
+            .iter()
+            .all(|f| f.default_fn.is_none() && has_default(&f.rust_type, ctx))
+        {
+            derive_default = true;
+            ctx.defaults.insert(name.to_string());
+        } else if let Some(code) = default_impl_code(name, &literal_fields, ctx) {
+            extra_helpers.push(code);
+            ctx.defaults.insert(name.to_string());
+        }
+    }
+    if ctx.options.with_builder {
+        let (builder, _) = ctx.claim_type_name(
+            &format!("{}Builder", name),
+            &serde_json::json!({ "x-builder-for": name }),
+        );
+        extra_helpers.push(builder_code(name, &builder, &literal_fields));
+    }
+
+    if !flattened {
+        ctx.literals
+            .insert(name.to_string(), LiteralForm::Struct(literal_fields));
+    }
+
+    // ✅ other if/then/else, dependentRequired, dependentSchemas → runtime checks
+    if let Some(method) = validate_conditions_fn(name, schema) {
+        extra_helpers.push(method);
     }
 
     // ✅ struct header + doc
@@ -415,348 +1621,2542 @@ pub fn extract_struct_recursive(
         }
     }
 
-    struct_lines.push("#[derive(Debug, Serialize, Deserialize)]".to_string());
+    if ctx.options.with_validation {
+        struct_lines.push("#[derive(Debug, Serialize, Deserialize, Validate)]".to_string());
+    } else {
+        struct_lines.push("#[derive(Debug, Serialize, Deserialize)]".to_string());
+    }
+    if let Some(rule) = rename_all {
+        struct_lines.push(format!("#[serde(rename_all = \"{}\")]", rule));
+    }
+    if deny_unknown_fields {
+        struct_lines.push("#[serde(deny_unknown_fields)]".to_string());
+    }
     struct_lines.push(format!("pub struct {} {{\n{}\n}}", name, fields.join("\n")));
 
     let mut struct_code = struct_lines.join("\n");
+    if derive_default {
+        struct_code = add_derives(&struct_code, &["Default"]);
+    }
     if !extra_helpers.is_empty() {
         struct_code.push_str("\n\n");
         struct_code.push_str(&extra_helpers.join("\n\n"));
     }
 
+    ctx.in_progress.pop();
     ctx.output.push(NamedStruct {
         name: name.to_string(),
         code: struct_code,
         output_path: output_path.clone(),
+        imports: vec![],
+        members: field_types(&fields),
     });
-    ctx.generated_defs.insert(name.to_string());
 }
 
-pub fn infer_rust_type(
-    prop: &Value,
-    key: &str,
-    ctx: &mut GeneratorContext,
-    definitions: &Value,
-    resolver: &mut RefResolver,
-    output_path: Option<String>,
-    with_docs: bool,
+/// `impl Default` for a struct: schema `default`/`const` helpers where present,
+/// `Default::default()` otherwise. Returns `None` (with a warning) when a field type
+/// has no `Default`, e.g. a generated enum.
+fn default_impl_code(
+    name: &str,
+    fields: &[LiteralField],
+    ctx: &GeneratorContext,
 ) -> Option<String> {
-    log_debug!("🧪 infer_rust_type: key = {}, prop = {}", key, prop);
-    if let Some(ref_val) = prop.get("$ref").and_then(|v| v.as_str()) {
-        return if ref_val.starts_with("#/") {
-            let name = ref_val.split('/').last()?.to_string();
-            if ctx.generated_defs.contains(&name) {
-                return Some(name);
-            }
-            let def = definitions.get(&name)?;
-            ctx.generated_defs.insert(name.clone());
-            extract_struct_recursive(
-                &name,
-                def,
-                ctx,
-                ref_val.to_string(),
-                definitions,
-                resolver,
-                output_path.clone(),
-                with_docs,
-            );
-            Some(name)
-        } else {
-            let resolved = resolver.resolve(ref_val)?;
-            log_debug!("🧩 RESOLVED: {} → {}", ref_val, resolved);
-            let name = to_pascal_case(Path::new(ref_val).file_stem()?.to_str()?);
-            if ctx.generated_defs.contains(&name) {
-                return Some(name);
+    let mut values = vec![];
+    for f in fields {
+        let value = match &f.default_fn {
+            Some(default_fn) => format!("{}()", default_fn),
+            None if has_default(&f.rust_type, ctx) => "Default::default()".to_string(),
+            None => {
+                log_warn!(
+                    "⚠️ `{}` gets no impl Default: field `{}` has type `{}` without one",
+                    name,
+                    f.field,
+                    f.rust_type
+                );
+                return None;
             }
-            let path_no_ext = Path::new(ref_val).with_extension("");
-            let ref_output_path = Some(path_no_ext.to_string_lossy().replace("\\", "/"));
+        };
+        values.push(format!("            {}: {},", f.field, value));
+    }
 
-            ctx.generated_defs.insert(name.clone());
-            extract_struct_recursive(
-                &name,
-                &resolved,
-                ctx,
-                ref_val.to_string(),
-                &Value::Null,
-                resolver,
-                ref_output_path.clone(),
-                with_docs,
-            );
-            Some(name)
+    let body = match values.as_slice() {
+        [] => format!("{} {{}}", name),
+        _ => format!("{} {{\n{}\n        }}", name, values.join("\n")),
+    };
+    Some(format!(
+        "impl Default for {} {{\n    fn default() -> Self {{\n        {}\n    }}\n}}",
+        name, body
+    ))
+}
+
+/// Whether `rust_type` implements `Default`: std types, chrono/uuid format types and
+/// generated types that got an `impl Default`.
+fn has_default(rust_type: &str, ctx: &GeneratorContext) -> bool {
+    let (head, args) = split_generic(rust_type);
+    match head {
+        "Option" | "Vec" | "HashMap" | "BTreeMap" | "HashSet" | "BTreeSet" => true,
+        "" | "Box" => args.iter().all(|arg| has_default(arg, ctx)),
+        "String" | "bool" | "f64" | "serde_json::Value" | "Uuid" | "DateTime" | "NaiveDate"
+        | "NaiveTime" => true,
+        name => is_integer_type(name) || ctx.defaults.contains(name),
+    }
+}
+
+/// `{name}::builder()` plus the `{builder}` type: one setter per field, and `build()`
+/// failing with the names of all missing required fields. Fields left unset fall
+/// back to their schema default, then to `None` (or empty for flattened maps).
+///
+/// The setters of fields named `build` or `builder` get a `with_` prefix.
+fn builder_code(name: &str, builder: &str, fields: &[LiteralField]) -> String {
+    let mut slots = vec![];
+    let mut setters = vec![];
+    let mut values = vec![];
+    let mut required = vec![];
+
+    for f in fields {
+        let value_type = f
+            .rust_type
+            .strip_prefix("Option<")
+            .and_then(|t| t.strip_suffix('>'))
+            .unwrap_or(&f.rust_type);
+        slots.push(format!("    {}: Option<{}>,", f.field, value_type));
+        let setter = match f.field.as_str() {
+            "build" | "builder" => format!("with_{}", f.field),
+            field => field.to_string(),
+        };
+        setters.push(format!(
+            "    pub fn {setter}(mut self, value: impl Into<{value_type}>) -> Self {{\n        self.{field} = Some(value.into());\n        self\n    }}",
+            field = f.field,
+        ));
+
+        let optional = f.rust_type.starts_with("Option<");
+        let value = match (&f.default_fn, optional) {
+            (Some(default_fn), true) => format!("self.{}.or_else({})", f.field, default_fn),
+            (Some(default_fn), false) => format!("self.{}.unwrap_or_else({})", f.field, default_fn),
+            (None, true) => format!("self.{}", f.field),
+            (None, false) if f.required => {
+                required.push(f);
+                f.field.clone()
+            }
+            (None, false) => format!("self.{}.unwrap_or_default()", f.field),
         };
+        values.push(if value == f.field {
+            format!("            {},", value)
+        } else {
+            format!("            {}: {},", f.field, value)
+        });
     }
 
-    if let Some(one_of) = prop.get("oneOf") {
-        return handle_one_of(
-            key,
-            one_of,
-            ctx,
-            definitions,
-            resolver,
-            output_path.clone(),
-            with_docs,
+    let value = match values.as_slice() {
+        [] => format!("{} {{}}", name),
+        _ => format!("{} {{\n{}\n        }}", name, values.join("\n")),
+    };
+    let slots = match slots.as_slice() {
+        [] => ";".to_string(),
+        _ => format!(" {{\n{}\n}}", slots.join("\n")),
+    };
+    let mut checks = String::new();
+    if !required.is_empty() {
+        let unset = required
+            .iter()
+            .map(|f| {
+                format!(
+                    "            ({:?}, self.{}.is_none()),",
+                    format!("`{}`", f.key),
+                    f.field
+                )
+            })
+            .collect::<Vec<_>>();
+        let (bindings, slots) = match required.as_slice() {
+            [f] => (format!("Some({})", f.field), format!("self.{}", f.field)),
+            _ => (
+                format!(
+                    "({})",
+                    required
+                        .iter()
+                        .map(|f| format!("Some({})", f.field))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                format!(
+                    "({})",
+                    required
+                        .iter()
+                        .map(|f| format!("self.{}", f.field))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+        };
+        checks = format!(
+            "        let missing = [\n{}\n        ]\n        .into_iter()\n        .filter_map(|(key, unset)| unset.then_some(key))\n        .collect::<Vec<_>>();\n        let {bindings} = {slots} else {{\n            return Err(format!(\"missing required fields: {{}}\", missing.join(\", \")));\n        }};\n",
+            unset.join("\n")
         );
     }
+    let mut methods = setters;
+    methods.push(format!(
+        "    /// Fails with the names of all required fields that weren't set.\n    pub fn build(self) -> Result<{name}, String> {{\n{checks}        Ok({value})\n    }}"
+    ));
 
-    if let Some(any_of) = prop.get("anyOf") {
-        return handle_any_of(
-            key,
-            any_of,
-            ctx,
-            definitions,
-            resolver,
-            output_path.clone(),
-            with_docs,
-        );
+    format!(
+        "impl {name} {{\n    pub fn builder() -> {builder} {{\n        Default::default()\n    }}\n}}\n\n/// Builder for [`{name}`], see `{name}::builder()`.\n#[derive(Debug, Default)]\npub struct {builder}{slots}\n\nimpl {builder} {{\n{}\n}}",
+        methods.join("\n\n")
+    )
+}
+
+/// Splits an object whose `if` tests one required, enum-valued property (`const` or `enum`)
+/// into `(tag, [(tag value, payload schema)])`, the payload being the object without the tag,
+/// merged with `then` or `else` depending on the value.
+///
+/// Returns `None` for anything the enum can't express, which then goes through
+/// `validate_conditions_fn` instead.
+fn conditional_variants(schema: &Value) -> Option<(String, Vec<(Value, Value)>)> {
+    let condition = schema.get("if")?.as_object()?;
+    let dependencies = ["dependentRequired", "dependentSchemas", "dependencies"];
+    if dependencies.iter().any(|k| schema.get(k).is_some())
+        || condition
+            .keys()
+            .any(|k| k != "properties" && k != "required")
+    {
+        return None;
     }
 
-    if let Some(all_of) = prop.get("allOf") {
-        return handle_all_of(
-            key,
-            all_of,
-            ctx,
-            definitions,
-            resolver,
-            output_path.clone(),
-            with_docs,
-        );
+    let tested = condition.get("properties")?.as_object()?;
+    let [(tag, test)] = tested.iter().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let matching = match (test.get("const"), test.get("enum")) {
+        (Some(value), _) => vec![value.clone()],
+        (None, Some(Value::Array(values))) => values.clone(),
+        _ => return None,
+    };
+
+    // every value of the tag must be known to name the `else` variants
+    let tag_values = schema
+        .get("properties")?
+        .get(tag)?
+        .get("enum")?
+        .as_array()?;
+    let required = schema.get("required").and_then(|r| r.as_array());
+    if !required.is_some_and(|r| r.iter().any(|r| r == tag.as_str()))
+        || !tag_values.iter().all(|v| v.is_string())
+    {
+        return None;
     }
 
-    match prop.get("type")?.as_str()? {
-        "string" => {
-            if let Some(enum_vals) = prop.get("enum") {
-                let enum_name = to_pascal_case(key);
-                let variants = enum_vals
-                    .as_array()?
-                    .iter()
-                    .filter_map(|v| v.as_str())
-                    .map(|v| format!("    {},", to_pascal_case(v)))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                let code = format!(
-                    "#[derive(Debug, Serialize, Deserialize)]\npub enum {} {{\n{}\n}}",
-                    enum_name, variants
-                );
-                ctx.output.push(NamedStruct {
-                    name: enum_name.clone(),
-                    code,
-                    output_path,
-                });
-                ctx.generated_defs.insert(enum_name.clone());
-                Some(enum_name)
+    let branch = |keyword: &str| match schema.get(keyword) {
+        None => Some(None),
+        Some(branch) => branch
+            .as_object()?
+            .keys()
+            .all(|k| ["properties", "required", "title", "description"].contains(&k.as_str()))
+            .then_some(Some(branch)),
+    };
+    let (then, otherwise) = (branch("then")?, branch("else")?);
+
+    let mut base = without_property(schema, tag);
+    if let Some(base) = base.as_object_mut() {
+        base.remove("if");
+        base.remove("then");
+        base.remove("else");
+    }
+
+    let variants = tag_values
+        .iter()
+        .map(|value| {
+            let branch = if matching.contains(value) {
+                then
             } else {
-                Some("String".to_string())
+                otherwise
+            };
+            let mut payload = base.clone();
+            if let Some(branch) = branch {
+                merge_conditional_branch(&mut payload, branch);
             }
-        }
-        "integer" => Some("i32".to_string()),
-        "number" => Some("f64".to_string()),
-        "boolean" => Some("bool".to_string()),
-        "array" => {
-            let items = prop.get("items")?;
+            (value.clone(), payload)
+        })
+        .collect();
+    Some((tag.clone(), variants))
+}
+
+/// Adds a `then`/`else`/`dependentSchemas` branch's properties (where `base` has none of
+/// that name) and `required` entries to `base`.
+fn merge_conditional_branch(base: &mut Value, branch: &Value) {
+    let Some(base) = base.as_object_mut() else {
+        return;
+    };
+    if let Some(properties) = branch.get("properties").and_then(|p| p.as_object()) {
+        let base_properties = base
+            .entry("properties")
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Some(base_properties) = base_properties.as_object_mut() {
+            for (key, prop) in properties {
+                base_properties
+                    .entry(key.clone())
+                    .or_insert_with(|| prop.clone());
+            }
+        }
+    }
+    if let Some(required) = branch.get("required").and_then(|r| r.as_array()) {
+        let base_required = base
+            .entry("required")
+            .or_insert_with(|| Value::Array(vec![]));
+        if let Some(base_required) = base_required.as_array_mut() {
+            for key in required {
+                if !base_required.contains(key) {
+                    base_required.push(key.clone());
+                }
+            }
+        }
+    }
+}
+
+/// `schema` with the properties only named in `then`/`else`/`dependentSchemas` added as
+/// optional fields, so values that satisfy those branches still deserialize.
+fn with_conditional_properties(schema: &Value) -> Value {
+    fn branches<'a>(schema: &'a Value, out: &mut Vec<&'a Value>) {
+        for keyword in ["then", "else"] {
+            if let Some(branch) = schema.get(keyword) {
+                out.push(branch);
+                branches(branch, out);
+            }
+        }
+        for keyword in ["dependentSchemas", "dependencies"] {
+            if let Some(deps) = schema.get(keyword).and_then(|d| d.as_object()) {
+                out.extend(deps.values().filter(|d| d.is_object()));
+            }
+        }
+    }
+
+    let mut parts = vec![];
+    branches(schema, &mut parts);
+    if parts.is_empty() {
+        return schema.clone();
+    }
+
+    let mut merged = schema.clone();
+    for part in parts {
+        let optional = part
+            .get("properties")
+            .map(|properties| serde_json::json!({ "properties": properties }));
+        if let Some(optional) = optional {
+            merge_conditional_branch(&mut merged, &optional);
+        }
+    }
+    merged
+}
+
+/// Emits the tagged enum for `conditional_variants`, one `{Enum}{Variant}` struct per shape.
+#[allow(clippy::too_many_arguments)]
+fn emit_conditional_enum(
+    enum_name: &str,
+    tag: &str,
+    variants: Vec<(Value, Value)>,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) {
+    let mut lines = vec![];
+    let mut members = vec![];
+    let mut payloads: Vec<(Value, String)> = vec![];
+    let mut taken = HashSet::new();
+    ctx.in_progress.push(enum_name.to_string());
+
+    for (i, (tag_value, payload)) in variants.into_iter().enumerate() {
+        let tag_value = tag_value.as_str().unwrap_or_default().to_string();
+        let var_name = pascal_ident(&tag_value).unwrap_or_else(|| format!("Variant{}", i + 1));
+        let var_name = unique_variant_name(var_name, &mut taken);
+
+        // values sharing a branch share its struct
+        let struct_name = match payloads.iter().find(|(shape, _)| *shape == payload) {
+            Some((_, struct_name)) => struct_name.clone(),
+            None => {
+                let (struct_name, exists) =
+                    ctx.claim_type_name(&format!("{}{}", enum_name, var_name), &payload);
+                if !exists {
+                    extract_struct_recursive(
+                        &struct_name,
+                        &payload,
+                        ctx,
+                        "#".to_string(),
+                        document,
+                        resolver,
+                        output_path.clone(),
+                        with_docs,
+                    );
+                }
+                payloads.push((payload, struct_name.clone()));
+                struct_name
+            }
+        };
+
+        if var_name != tag_value {
+            lines.push(format!("    #[serde(rename = {:?})]", tag_value));
+        }
+        lines.push(format!("    {}({}),", var_name, struct_name));
+        members.push(struct_name);
+    }
+
+    let code = format!(
+        "#[derive(Debug, Serialize, Deserialize)]\n#[serde(tag = {:?})]\npub enum {} {{\n{}\n}}",
+        tag,
+        enum_name,
+        lines.join("\n")
+    );
+
+    ctx.in_progress.pop();
+    ctx.output.push(NamedStruct {
+        name: enum_name.to_string(),
+        code,
+        output_path,
+        imports: vec![],
+        members,
+    });
+}
+
+/// Renders `impl {name} {{ pub fn validate_conditions(&self) ... }}` checking `if`/`then`/`else`,
+/// `dependentRequired` and `dependentSchemas` (or draft-07 `dependencies`) on the serialized value.
+///
+/// Only `required` and property `const`/`enum` tests are translated; rules using anything
+/// else are skipped with a warning.
+fn validate_conditions_fn(name: &str, schema: &Value) -> Option<String> {
+    let mut checks = vec![];
+
+    if schema.get("if").is_some() {
+        let mut conditions = 0;
+        match if_then_else_checks(schema, &[], &mut conditions) {
+            Ok(lines) => checks.extend(lines),
+            Err(keyword) => log_warn!(
+                "⚠️ `{}`: can't check `if`/`then`/`else` using `{}`, skipping the rule",
+                name,
+                keyword
+            ),
+        }
+    }
+
+    let mut dependent_required = vec![];
+    let mut dependent_schemas = vec![];
+    for keyword in ["dependentRequired", "dependentSchemas", "dependencies"] {
+        for (key, dep) in schema
+            .get(keyword)
+            .and_then(|d| d.as_object())
+            .into_iter()
+            .flatten()
+        {
+            match dep {
+                Value::Array(keys) => dependent_required.push((key, keys)),
+                dep => dependent_schemas.push((key, dep)),
+            }
+        }
+    }
+
+    for (key, keys) in dependent_required {
+        let present = format!("!value[{:?}].is_null()", key);
+        for dependent in keys.iter().filter_map(|k| k.as_str()) {
+            checks.push(condition_check(
+                &[present.clone(), format!("value[{:?}].is_null()", dependent)],
+                &format!("`{}` is required when `{}` is present", dependent, key),
+            ));
+        }
+    }
+
+    for (key, dep) in dependent_schemas {
+        let guard = [format!("!value[{:?}].is_null()", key)];
+        let context = format!("when `{}` is present", key);
+        match subschema_checks(dep, &guard, &context) {
+            Ok(lines) => checks.extend(lines),
+            Err(keyword) => log_warn!(
+                "⚠️ `{}`: can't check the dependent schema of `{}` using `{}`, skipping the rule",
+                name,
+                key,
+                keyword
+            ),
+        }
+    }
+
+    if checks.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "impl {name} {{\n    /// Checks the schema's conditional rules (`if`/`then`/`else`, `dependentRequired`,\n    /// `dependentSchemas`) that serde can't enforce while deserializing.\n    pub fn validate_conditions(&self) -> Result<(), String> {{\n        let value = serde_json::to_value(self).map_err(|e| e.to_string())?;\n{}\n        Ok(())\n    }}\n}}",
+        checks.join("\n")
+    ))
+}
+
+/// Statements for one `if`/`then`/`else`, nested ones in `then`/`else` included: the
+/// condition is bound to a `condition*` variable and each branch check runs under it
+/// (and under `guard`). `Err` names the first keyword that can't be translated.
+fn if_then_else_checks(
+    schema: &Value,
+    guard: &[String],
+    conditions: &mut usize,
+) -> Result<Vec<String>, String> {
+    let Some(condition) = schema.get("if") else {
+        return Ok(vec![]);
+    };
+    let tests = subschema_tests(condition)?
+        .into_iter()
+        .map(|test| test.holds)
+        .collect::<Vec<_>>();
+
+    *conditions += 1;
+    let var = match *conditions {
+        1 => "condition".to_string(),
+        n => format!("condition_{}", n),
+    };
+
+    let mut lines = vec![];
+    for (keyword, holds, context) in [
+        ("then", var.clone(), "when the `if` condition holds"),
+        ("else", format!("!{}", var), "when the `if` condition fails"),
+    ] {
+        let Some(branch) = schema.get(keyword) else {
+            continue;
+        };
+        let guard = guard
+            .iter()
+            .cloned()
+            .chain(std::iter::once(holds))
+            .collect::<Vec<_>>();
+        lines.extend(subschema_checks(branch, &guard, context)?);
+        lines.extend(if_then_else_checks(branch, &guard, conditions)?);
+    }
+
+    if lines.is_empty() {
+        return Ok(lines);
+    }
+    let condition = if tests.is_empty() {
+        "true".to_string()
+    } else {
+        conjoin(&tests)
+    };
+    lines.insert(0, format!("        let {} = {};", var, condition));
+    Ok(lines)
+}
+
+/// `if guard && violation {{ return Err(...) }}` for each test of `schema`.
+fn subschema_checks(
+    schema: &Value,
+    guard: &[String],
+    context: &str,
+) -> Result<Vec<String>, String> {
+    Ok(subschema_tests(schema)?
+        .into_iter()
+        .map(|test| {
+            let terms = guard
+                .iter()
+                .cloned()
+                .chain(std::iter::once(test.violated))
+                .collect::<Vec<_>>();
+            condition_check(&terms, &format!("{} {}", test.message, context))
+        })
+        .collect())
+}
+
+/// `if a && b {{ return Err(message) }}`.
+fn condition_check(terms: &[String], message: &str) -> String {
+    format!(
+        "        if {} {{\n            return Err({:?}.to_string());\n        }}",
+        conjoin(terms),
+        message
+    )
+}
+
+/// Joins boolean expressions with `&&`, parenthesizing `||` terms.
+fn conjoin(terms: &[String]) -> String {
+    match terms {
+        [single] => single.clone(),
+        _ => terms
+            .iter()
+            .map(|t| {
+                if t.contains("||") {
+                    format!("({})", t)
+                } else {
+                    t.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" && "),
+    }
+}
+
+/// One translated test of a conditional subschema, over the serialized `value`.
+struct ConditionTest {
+    /// Expression that is true when the test passes
+    holds: String,
+    /// Expression that is true when the test fails
+    violated: String,
+    message: String,
+}
+
+/// Tests for each `required` key and property `const`/`enum` in `schema`. Absent
+/// properties pass their `const`/`enum` test, as in JSON Schema.
+fn subschema_tests(schema: &Value) -> Result<Vec<ConditionTest>, String> {
+    const IGNORED: [&str; 5] = ["title", "description", "$comment", "type", "if"];
+    let mut tests = vec![];
+
+    for (keyword, value) in schema.as_object().into_iter().flatten() {
+        match keyword.as_str() {
+            "required" => {
+                for key in value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|k| k.as_str())
+                {
+                    tests.push(ConditionTest {
+                        holds: format!("!value[{:?}].is_null()", key),
+                        violated: format!("value[{:?}].is_null()", key),
+                        message: format!("`{}` is required", key),
+                    });
+                }
+            }
+            "properties" => {
+                for (key, prop) in value.as_object().into_iter().flatten() {
+                    for (keyword, expected) in prop.as_object().into_iter().flatten() {
+                        let (matches, mismatch, message) = match (keyword.as_str(), expected) {
+                            ("const", expected) => (
+                                format!("value[{:?}] == serde_json::json!({})", key, expected),
+                                format!("value[{:?}] != serde_json::json!({})", key, expected),
+                                format!("`{}` must be {}", key, expected),
+                            ),
+                            ("enum", Value::Array(allowed)) => {
+                                let contains = format!(
+                                    "[{}].contains(&value[{:?}])",
+                                    allowed
+                                        .iter()
+                                        .map(|v| format!("serde_json::json!({})", v))
+                                        .collect::<Vec<_>>()
+                                        .join(", "),
+                                    key
+                                );
+                                (
+                                    contains.clone(),
+                                    format!("!{}", contains),
+                                    format!("`{}` must be one of {}", key, expected),
+                                )
+                            }
+                            (keyword, _) if IGNORED.contains(&keyword) => continue,
+                            (keyword, _) => return Err(keyword.to_string()),
+                        };
+                        let absent = format!("value[{:?}].is_null()", key);
+                        tests.push(ConditionTest {
+                            holds: format!("{} || {}", absent, matches),
+                            violated: format!("!{} && {}", absent, mismatch),
+                            message,
+                        });
+                    }
+                }
+            }
+            "then" | "else" => {}
+            keyword if IGNORED.contains(&keyword) => {}
+            keyword => return Err(keyword.to_string()),
+        }
+    }
+    Ok(tests)
+}
+
+/// Drops a `Box<...>` added by `box_if_recursive` where the type already sits behind
+/// a heap-allocated container such as `Vec` or `HashMap`.
+fn unbox(rust_type: String) -> String {
+    match rust_type
+        .strip_prefix("Box<")
+        .and_then(|t| t.strip_suffix('>'))
+    {
+        Some(inner) => inner.to_string(),
+        None => rust_type,
+    }
+}
+
+/// Translates schema constraints on a property into a `#[validate(...)]` attribute.
+///
+/// Also returns the `static` regex a `pattern` needs. Rules are only emitted when they
+/// fit `rust_type`, e.g. `email` is skipped once `format` mapped the field to another type.
+fn validation_attr(
+    struct_name: &str,
+    field_name: &str,
+    prop: &Value,
+    rust_type: &str,
+    ctx: &mut GeneratorContext,
+) -> (Option<String>, Option<String>) {
+    let mut rules = vec![];
+    let mut regex_static = None;
+    // `type: ["string", "null"]` arrives as `Option<String>`
+    let rust_type = rust_type
+        .strip_prefix("Option<")
+        .and_then(|t| t.strip_suffix('>'))
+        .unwrap_or(rust_type);
+
+    let length = |min_key: &str, max_key: &str| {
+        let bounds = [("min", min_key), ("max", max_key)]
+            .iter()
+            .filter_map(|(arg, key)| {
+                prop.get(*key)
+                    .and_then(|v| v.as_u64())
+                    .map(|n| format!("{} = {}", arg, n))
+            })
+            .collect::<Vec<_>>();
+        (!bounds.is_empty()).then(|| format!("length({})", bounds.join(", ")))
+    };
+
+    match rust_type {
+        "String" => {
+            rules.extend(length("minLength", "maxLength"));
+            match prop.get("format").and_then(|f| f.as_str()) {
+                Some("email") => rules.push("email".to_string()),
+                Some("uri") | Some("url") => rules.push("url".to_string()),
+                _ => {}
+            }
+            if let Some(pattern) = prop.get("pattern").and_then(|p| p.as_str()) {
+                let static_name = format!(
+                    "{}_{}_PATTERN",
+                    to_snake_case(struct_name),
+                    field_name.trim_start_matches("r#")
+                )
+                .to_uppercase();
+                let lazy_lock = ctx.use_type("std::sync::LazyLock");
+                let regex = ctx.use_type("regex::Regex");
+                regex_static = Some(format!(
+                    "static {}: {}<{}> = {}::new(|| {}::new({:?}).unwrap());",
+                    static_name, lazy_lock, regex, lazy_lock, regex, pattern
+                ));
+                rules.push(format!("regex(path = *{})", static_name));
+            }
+        }
+        t if t == "f64" || is_integer_type(t) => {
+            let mut bounds = vec![];
+            if t == "f64" {
+                let NumberBounds { min, max } = NumberBounds::of(prop);
+                for (bound, inclusive, exclusive) in
+                    [(min, "min", "exclusive_min"), (max, "max", "exclusive_max")]
+                {
+                    if let Some(Bound {
+                        value,
+                        exclusive: is_exclusive,
+                    }) = bound
+                    {
+                        let arg = if is_exclusive { exclusive } else { inclusive };
+                        bounds.push(format!("{} = {:?}", arg, value));
+                    }
+                }
+            } else {
+                let (min, max) = NumberBounds::of(prop).integer_checks(t);
+                if let Some(min) = min {
+                    bounds.push(format!("min = {}", min));
+                }
+                if let Some(max) = max {
+                    bounds.push(format!("max = {}", max));
+                }
+            }
+            if !bounds.is_empty() {
+                rules.push(format!("range({})", bounds.join(", ")));
+            }
+        }
+        _ if ["Vec<", "BTreeSet<", "HashSet<"]
+            .iter()
+            .any(|collection| rust_type.starts_with(collection)) =>
+        {
+            rules.extend(length("minItems", "maxItems"));
+        }
+        _ => {}
+    }
+
+    // Recurse into generated structs so `.validate()` covers the whole request
+    let inner = rust_type
+        .trim_start_matches("Option<")
+        .trim_start_matches("Vec<")
+        .trim_start_matches("BTreeSet<")
+        .trim_start_matches("HashSet<")
+        .trim_start_matches("Box<")
+        .trim_end_matches('>');
+    // A recursive struct is still in progress, but it derives `Validate` like every struct
+    if ctx.structs.contains(inner)
+        || ctx
+            .output
+            .iter()
+            .any(|s| s.name == inner && s.code.contains("Validate)]"))
+    {
+        rules.push("nested".to_string());
+    }
+
+    let attr = (!rules.is_empty()).then(|| format!("    #[validate({})]", rules.join(", ")));
+    (attr, regex_static)
+}
+
+/// Generates (or reuses) the Rust type a `$ref` points to.
+///
+/// Internal refs are resolved inside `document` by JSON pointer or `$anchor`;
+/// anything else is loaded through the `RefResolver` and written to its own module.
+fn resolve_ref_type(
+    ref_val: &str,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) -> Option<String> {
+    if ref_val == "#" {
+        return Some(ctx.box_if_recursive(&ctx.root_name));
+    }
+
+    let (file_path, fragment) = ref_val.split_once('#').unwrap_or((ref_val, ""));
+    let fragment = percent_decode(fragment);
+
+    if file_path.is_empty() {
+        // Internal refs are keyed by the resource they live in
+        let cache_key = format!("{}#{}", ctx.base.uri, fragment);
+        if let Some(rust_type) = ctx.resolved_refs.get(&cache_key) {
+            return Some(ctx.box_if_recursive(rust_type));
+        }
+
+        let name = type_name_for_fragment(&fragment)?;
+        let target = resolve_fragment(document, &fragment)?.clone();
+        let rust_type = generate_ref_target(
+            &name,
+            &target,
+            ctx,
+            document,
+            resolver,
+            output_path,
+            with_docs,
+        )?;
+        ctx.resolved_refs.insert(cache_key, rust_type.clone());
+        return Some(rust_type);
+    }
+
+    let ResolvedRef { resource, base } = resolver.resolve(file_path, &ctx.base)?;
+    let cache_key = format!("{}#{}", base.uri, fragment);
+    if let Some(rust_type) = ctx.resolved_refs.get(&cache_key) {
+        return Some(ctx.box_if_recursive(rust_type));
+    }
+
+    let resolved = resolve_fragment(&resource, &fragment)?.clone();
+    log_debug!("🧩 RESOLVED: {} → {} in {}", ref_val, resolved, base.file);
+
+    let file_name = match resource.get("x-rust-name").and_then(|n| n.as_str()) {
+        Some(name) => name.to_string(),
+        None => to_pascal_case(Path::new(&base.file).file_stem()?.to_str()?),
+    };
+    let name = if fragment.is_empty() {
+        file_name.clone()
+    } else {
+        type_name_for_fragment(&fragment)?
+    };
+    let path_no_ext = Path::new(&base.file).with_extension("");
+    let ref_output_path = Some(path_no_ext.to_string_lossy().replace("\\", "/"));
+
+    let outer_root = std::mem::replace(&mut ctx.root_name, file_name);
+    let outer_base = std::mem::replace(&mut ctx.base, base);
+    let rust_type = generate_ref_target(
+        &name,
+        &resolved,
+        ctx,
+        &resource,
+        resolver,
+        ref_output_path,
+        with_docs,
+    );
+    ctx.root_name = outer_root;
+    ctx.base = outer_base;
+
+    let rust_type = rust_type?;
+    ctx.resolved_refs.insert(cache_key, rust_type.clone());
+    Some(rust_type)
+}
+
+/// Generates the schema a `$ref` resolved to under the name derived from the ref.
+///
+/// Object schemas become a struct of that name; anything else (enums, unions, arrays,
+/// primitives) goes through `infer_rust_type` so e.g. an integer definition stays `i32`.
+fn generate_ref_target(
+    name: &str,
+    target: &Value,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) -> Option<String> {
+    if let Some(rust_type) = target.get("x-rust-type").and_then(|t| t.as_str()) {
+        return Some(ctx.use_type(rust_type));
+    }
+
+    let (name, exists) = ctx.claim_type_name(name, target);
+    if exists {
+        return Some(ctx.box_if_recursive(&name));
+    }
+
+    let is_struct = target.get("properties").is_some() || target.get("patternProperties").is_some();
+    if is_struct {
+        extract_struct_recursive(
+            &name,
+            target,
+            ctx,
+            "#".to_string(),
+            document,
+            resolver,
+            output_path,
+            with_docs,
+        );
+        return Some(name);
+    }
+
+    // Enums and unions claim their own name from the same schema; primitives claim none
+    ctx.type_shapes.remove(&name);
+    let rust_type = infer_rust_type(
+        target,
+        &name,
+        ctx,
+        document,
+        resolver,
+        output_path,
+        with_docs,
+    )
+    .unwrap_or_else(|| "serde_json::Value".to_string());
+    Some(rust_type)
+}
+
+/// Looks up a URI fragment in `document`: a JSON pointer (`/$defs/a~1b`) or a plain-name
+/// anchor declared with `$anchor: "name"` or draft-07 style `$id: "#name"`.
+fn resolve_fragment<'v>(document: &'v Value, fragment: &str) -> Option<&'v Value> {
+    if fragment.is_empty() || fragment.starts_with('/') {
+        // `Value::pointer` unescapes `~1` → `/` and `~0` → `~` per segment
+        return document.pointer(fragment);
+    }
+    find_anchor(document, fragment)
+}
+
+fn find_anchor<'v>(schema: &'v Value, anchor: &str) -> Option<&'v Value> {
+    match schema {
+        Value::Object(map) => {
+            let declares = map.get("$anchor").and_then(|a| a.as_str()) == Some(anchor)
+                || map.get("$id").and_then(|id| id.as_str()) == Some(&format!("#{}", anchor));
+            if declares {
+                return Some(schema);
+            }
+            map.values().find_map(|v| find_anchor(v, anchor))
+        }
+        Value::Array(items) => items.iter().find_map(|v| find_anchor(v, anchor)),
+        _ => None,
+    }
+}
+
+/// Derives a stable type name from a ref fragment, skipping container keywords:
+/// `/definitions/Foo` → `Foo`, `/$defs/foo~1bar` → `FooBar`, `/properties/a/items` → `AItems`,
+/// `myAnchor` → `MyAnchor`.
+fn type_name_for_fragment(fragment: &str) -> Option<String> {
+    let Some(pointer) = fragment.strip_prefix('/') else {
+        return pascal_ident(fragment);
+    };
+
+    let name = pointer
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .filter(|segment| {
+            !matches!(
+                segment.as_str(),
+                "definitions" | "$defs" | "properties" | "patternProperties"
+            )
+        })
+        .filter_map(|segment| pascal_ident(&segment))
+        .collect::<String>();
+
+    (!name.is_empty()).then_some(name)
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub fn infer_rust_type(
+    prop: &Value,
+    key: &str,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) -> Option<String> {
+    log_debug!("🧪 infer_rust_type: key = {}, prop = {}", key, prop);
+    // ✅ x-rust-type pins the field to an existing type, skipping inference
+    if let Some(rust_type) = prop.get("x-rust-type").and_then(|t| t.as_str()) {
+        return Some(ctx.use_type(rust_type));
+    }
+
+    // An embedded `$id` starts a new resource: its refs resolve against it, not the document
+    if let Some(uri) = declared_base(prop, &ctx.base.uri)
+        && uri != ctx.base.uri
+    {
+        let base = SchemaBase {
+            uri,
+            file: ctx.base.file.clone(),
+        };
+        let outer_base = std::mem::replace(&mut ctx.base, base);
+        let rust_type = infer_rust_type(prop, key, ctx, prop, resolver, output_path, with_docs);
+        ctx.base = outer_base;
+        return rust_type;
+    }
+
+    if let Some(ref_val) = prop.get("$ref").and_then(|v| v.as_str()) {
+        return resolve_ref_type(ref_val, ctx, document, resolver, output_path, with_docs);
+    }
+
+    if prop.get("oneOf").is_some() {
+        return handle_one_of(
+            key,
+            prop,
+            ctx,
+            document,
+            resolver,
+            output_path.clone(),
+            with_docs,
+        );
+    }
+
+    if prop.get("anyOf").is_some() {
+        return handle_any_of(
+            key,
+            prop,
+            ctx,
+            document,
+            resolver,
+            output_path.clone(),
+            with_docs,
+        );
+    }
+
+    if prop.get("allOf").is_some() {
+        return handle_all_of(
+            key,
+            prop,
+            ctx,
+            document,
+            resolver,
+            output_path.clone(),
+            with_docs,
+        );
+    }
+
+    if prop.get("enum").is_some_and(|e| e.is_array()) {
+        return handle_enum(key, prop, ctx, output_path);
+    }
+
+    if prop.get("const").is_some() {
+        return handle_const(key, prop, ctx, output_path);
+    }
+
+    if prop.get("type").is_some_and(|t| t.is_array()) {
+        return handle_multi_type(key, prop, ctx, document, resolver, output_path, with_docs);
+    }
+
+    match prop.get("type")?.as_str()? {
+        "string" => {
+            let format_type = prop
+                .get("format")
+                .and_then(|f| f.as_str())
+                .and_then(|f| ctx.options.format_types.get(f));
+
+            if let Some(rust_type) = format_type {
+                Some(ctx.use_type(rust_type))
+            } else {
+                Some(handle_newtype(key, prop, "String", ctx, output_path))
+            }
+        }
+        "integer" => Some(handle_newtype(
+            key,
+            prop,
+            integer_type(prop),
+            ctx,
+            output_path,
+        )),
+        "number" => Some(handle_newtype(key, prop, "f64", ctx, output_path)),
+        "boolean" => Some("bool".to_string()),
+        "array" => {
+            if prop.get("prefixItems").is_some() || prop.get("items").is_some_and(|i| i.is_array())
+            {
+                return handle_tuple(key, prop, ctx, document, resolver, output_path, with_docs);
+            }
+            let items = prop.get("items")?;
             let inner = infer_rust_type(
                 items,
                 &format!("{}Item", key),
                 ctx,
-                definitions,
+                document,
                 resolver,
                 output_path.clone(),
                 with_docs,
             )?;
+            let inner = unbox(inner);
+            if prop.get("uniqueItems") == Some(&Value::Bool(true))
+                && ctx.options.set_type != SetType::Vec
+            {
+                if let Some(set) = ctx.set_type_for(&inner) {
+                    return Some(set);
+                }
+                log_warn!(
+                    "⚠️ `{}` has uniqueItems but `{}` can't derive the traits a set needs, keeping Vec",
+                    key,
+                    inner
+                );
+            }
             Some(format!("Vec<{}>", inner))
         }
-        "object" => {
-            if let Some(ap) = prop.get("additionalProperties") {
-                let inner_type = infer_rust_type(
-                    ap,
-                    &format!("{}Value", key),
-                    ctx,
-                    definitions,
-                    resolver,
-                    output_path.clone(),
-                    with_docs,
+        "object" => {
+            let has_fields =
+                prop.get("properties").is_some() || prop.get("patternProperties").is_some();
+            if let Some(ap) = prop
+                .get("additionalProperties")
+                .filter(|ap| !has_fields && **ap != Value::Bool(false))
+            {
+                let inner_type = infer_rust_type(
+                    ap,
+                    &format!("{}Value", key),
+                    ctx,
+                    document,
+                    resolver,
+                    output_path.clone(),
+                    with_docs,
+                )
+                .unwrap_or_else(|| "serde_json::Value".to_string());
+                return Some(format!("Option<HashMap<String, {}>>", unbox(inner_type)));
+            }
+            if has_fields {
+                let (sub_name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+                if !exists {
+                    extract_struct_recursive(
+                        &sub_name,
+                        prop,
+                        ctx,
+                        "#".to_string(),
+                        document,
+                        resolver,
+                        output_path.clone(),
+                        with_docs,
+                    );
+                }
+                return Some(ctx.box_if_recursive(&sub_name));
+            }
+            Some("serde_json::Value".to_string())
+        }
+        _ => Some("serde_json::Value".to_string()),
+    }
+}
+
+/// Name of the `default`/`const` helper for a field, scoped by its struct so two structs
+/// in one file can both default a `status` field.
+fn generate_default_function(struct_name: &str, field_name: &str, prop: &Value) -> Option<String> {
+    if prop.get("default").is_some() || prop.get("const").is_some() {
+        Some(format!(
+            "default_{}_{}",
+            to_snake_case(struct_name),
+            to_snake_case(field_name)
+        ))
+    } else {
+        None
+    }
+}
+
+/// Renders the `default_*` helper for a field; it returns exactly the field's type,
+/// so an optional field's helper returns `Option<T>` and wraps the value in `Some`.
+///
+/// Returns `None` (no helper, with a warning) when the value can't be written as that type.
+fn default_fn_code_string(
+    fn_name: &str,
+    field_name: &str,
+    prop: &Value,
+    field_type: &str,
+    ctx: &GeneratorContext,
+) -> Option<String> {
+    let val = prop.get("default").or_else(|| prop.get("const"))?;
+    let Some(rendered) = render_default_value(val, field_type, ctx) else {
+        log_warn!(
+            "⚠️ default {} of `{}` doesn't fit `{}`, skipping its default helper",
+            val,
+            field_name,
+            field_type
+        );
+        return None;
+    };
+
+    Some(format!(
+        "fn {}() -> {} {{\n    {}\n}}",
+        fn_name, field_type, rendered
+    ))
+}
+
+/// An expression for `v` as a value of `rust_type`: literals for primitives (`18` as `f64`
+/// → `18.0`), `vec![...]`, sets, `HashMap::from(...)`, tuples, `Some`/`None`, struct
+/// literals and enum variants for generated types. Types it can't spell out (format types,
+/// untagged enums, structs with flattened fields) are deserialized from the JSON value.
+///
+/// Returns `None` when the value doesn't match the type, e.g. a string for an integer.
+fn render_default_value(v: &Value, rust_type: &str, ctx: &GeneratorContext) -> Option<String> {
+    let (head, args) = split_generic(rust_type);
+    let render_all = |items: &[Value], item_type: &str| {
+        items
+            .iter()
+            .map(|item| render_default_value(item, item_type, ctx))
+            .collect::<Option<Vec<_>>>()
+    };
+
+    match (head, args.as_slice(), v) {
+        ("Option", _, Value::Null) => Some("None".to_string()),
+        ("Option", [inner], _) => Some(format!("Some({})", render_default_value(v, inner, ctx)?)),
+        ("Box", [inner], _) => Some(format!(
+            "Box::new({})",
+            render_default_value(v, inner, ctx)?
+        )),
+        ("String", _, Value::String(s)) => Some(format!("{:?}.to_string()", s)),
+        ("f64", _, Value::Number(n)) => Some(format!("{:?}", n.as_f64()?)),
+        (int, _, Value::Number(n)) if is_integer_type(int) => {
+            let n = integer_literal(v)?;
+            let (min, max) = integer_range(int);
+            (min..=max).contains(&n).then(|| n.to_string())
+        }
+        ("bool", _, Value::Bool(b)) => Some(b.to_string()),
+        ("Vec", [item], Value::Array(items)) => {
+            Some(format!("vec![{}]", render_all(items, item)?.join(", ")))
+        }
+        (set @ ("BTreeSet" | "HashSet"), [item], Value::Array(items)) => {
+            let items = render_all(items, item)?;
+            Some(if items.is_empty() {
+                format!("{}::new()", set)
+            } else {
+                format!("{}::from([{}])", set, items.join(", "))
+            })
+        }
+        ("HashMap", [_, value_type], Value::Object(map)) => {
+            let entries = map
+                .iter()
+                .map(|(key, value)| {
+                    let value = render_default_value(value, value_type, ctx)?;
+                    Some(format!("({:?}.to_string(), {})", key, value))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(if entries.is_empty() {
+                "HashMap::new()".to_string()
+            } else {
+                format!("HashMap::from([{}])", entries.join(", "))
+            })
+        }
+        ("", item_types, Value::Array(items)) if item_types.len() == items.len() => {
+            let items = items
+                .iter()
+                .zip(item_types)
+                .map(|(item, item_type)| render_default_value(item, item_type, ctx))
+                .collect::<Option<Vec<_>>>()?;
+            Some(match items.as_slice() {
+                [single] => format!("({},)", single),
+                _ => format!("({})", items.join(", ")),
+            })
+        }
+        ("serde_json::Value", _, _) => Some(format!("serde_json::json!({})", v)),
+        (name, _, _) if ctx.literals.contains_key(name) || !is_builtin_type(name) => {
+            render_generated_value(v, name, ctx)
+        }
+        _ => None,
+    }
+}
+
+/// A struct literal or enum variant for a generated type, falling back to deserializing
+/// the JSON value when the type has no [`LiteralForm`] or the value doesn't line up with it.
+fn render_generated_value(v: &Value, name: &str, ctx: &GeneratorContext) -> Option<String> {
+    let deserialized = || {
+        format!(
+            "serde_json::from_value(serde_json::json!({})).expect(\"default is a valid {}\")",
+            v, name
+        )
+    };
+
+    match (ctx.literals.get(name), v) {
+        // an unlisted value may still land in a `--enum-fallback` variant
+        (Some(LiteralForm::Enum(variants)), _) => Some(
+            variants
+                .iter()
+                .find(|(wire, _)| wire == v)
+                .map(|(_, variant)| format!("{}::{}", name, variant))
+                .unwrap_or_else(deserialized),
+        ),
+        (Some(LiteralForm::Struct(fields)), Value::Object(map)) => {
+            let fields = fields
+                .iter()
+                .map(|f| {
+                    let value = match (map.get(&f.key), &f.default_fn) {
+                        (Some(value), _) => render_default_value(value, &f.rust_type, ctx)?,
+                        (None, Some(default_fn)) => format!("{}()", default_fn),
+                        (None, None) if f.rust_type.starts_with("Option<") => "None".to_string(),
+                        (None, None) => return None,
+                    };
+                    Some(format!("{}: {}", f.field, value))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(match fields.as_slice() {
+                [] => format!("{} {{}}", name),
+                _ => format!("{} {{ {} }}", name, fields.join(", ")),
+            })
+        }
+        (Some(LiteralForm::Struct(_)), _) => None,
+        (Some(LiteralForm::Unit(value)), _) => (value == v).then(|| name.to_string()),
+        (None, _) => Some(deserialized()),
+    }
+}
+
+/// Primitive and std types `render_default_value` spells out itself; anything else is
+/// a generated or mapped (`format`) type.
+fn is_builtin_type(name: &str) -> bool {
+    is_integer_type(name)
+        || matches!(
+            name,
+            "" | "String"
+                | "f64"
+                | "bool"
+                | "Option"
+                | "Box"
+                | "Vec"
+                | "BTreeSet"
+                | "HashSet"
+                | "HashMap"
+                | "serde_json::Value"
+        )
+}
+
+/// Picks the integer type for `type: integer`: an explicit `format` such as `int64` or
+/// `uint32` wins; otherwise the smallest type holding `minimum..=maximum` and the `default`,
+/// unsigned when the minimum is non-negative. Without bounds it stays `i32`.
+fn integer_type(prop: &Value) -> &'static str {
+    let by_format = prop
+        .get("format")
+        .and_then(|f| f.as_str())
+        .and_then(|f| match f {
+            "int8" => Some("i8"),
+            "int16" => Some("i16"),
+            "int32" => Some("i32"),
+            "int64" => Some("i64"),
+            "uint8" => Some("u8"),
+            "uint16" => Some("u16"),
+            "uint32" => Some("u32"),
+            "uint64" => Some("u64"),
+            _ => None,
+        });
+    if let Some(rust_type) = by_format {
+        return rust_type;
+    }
+
+    let (mut min, mut max) = NumberBounds::of(prop).integer();
+    // A `default` outside the bounds still has to fit the type
+    let default = prop.get("default").and_then(integer_literal);
+    if let Some(default) = default {
+        min = min.map(|min| min.min(default));
+        max = max.map(|max| max.max(default));
+    }
+
+    match (min, max) {
+        (Some(min), max) if min >= 0 => match max {
+            Some(max) if max <= u8::MAX.into() => "u8",
+            Some(max) if max <= u16::MAX.into() => "u16",
+            Some(max) if max <= u32::MAX.into() => "u32",
+            _ => "u64",
+        },
+        (min, max) => {
+            let fits_i32 = [min, max, default]
+                .into_iter()
+                .flatten()
+                .all(|n| (i32::MIN.into()..=i32::MAX.into()).contains(&n));
+            if fits_i32 { "i32" } else { "i64" }
+        }
+    }
+}
+
+/// A whole JSON number as `i128`, covering both `i64` and `u64` values.
+fn integer_literal(v: &Value) -> Option<i128> {
+    v.as_i64()
+        .map(i128::from)
+        .or_else(|| v.as_u64().map(i128::from))
+}
+
+/// One end of a number's range; `exclusive` when `value` itself is out of range.
+#[derive(Clone, Copy)]
+struct Bound {
+    value: f64,
+    exclusive: bool,
+}
+
+/// The effective `minimum`/`maximum` of a number schema.
+///
+/// Draft-04 makes `minimum`/`maximum` exclusive with a boolean `exclusiveMinimum`/
+/// `exclusiveMaximum`; from draft-06 on those keywords carry their own number. When a
+/// schema has both, the tighter bound wins.
+struct NumberBounds {
+    min: Option<Bound>,
+    max: Option<Bound>,
+}
+
+impl NumberBounds {
+    fn of(prop: &Value) -> Self {
+        let bounds = |key: &str, exclusive_key: &str| {
+            let exclusive = prop.get(exclusive_key);
+            let inclusive = prop.get(key).and_then(|n| n.as_f64()).map(|value| Bound {
+                value,
+                exclusive: exclusive.and_then(|v| v.as_bool()) == Some(true),
+            });
+            let exclusive = exclusive.and_then(|n| n.as_f64()).map(|value| Bound {
+                value,
+                exclusive: true,
+            });
+            inclusive.into_iter().chain(exclusive)
+        };
+        NumberBounds {
+            min: bounds("minimum", "exclusiveMinimum").max_by(|a, b| {
+                a.value
+                    .total_cmp(&b.value)
+                    .then(a.exclusive.cmp(&b.exclusive))
+            }),
+            max: bounds("maximum", "exclusiveMaximum").min_by(|a, b| {
+                a.value
+                    .total_cmp(&b.value)
+                    .then(b.exclusive.cmp(&a.exclusive))
+            }),
+        }
+    }
+
+    /// The bounds as inclusive integers, so `> 1.5` becomes `>= 2` and `< 10` becomes `<= 9`.
+    fn integer(&self) -> (Option<i128>, Option<i128>) {
+        let min = self.min.map(|b| {
+            if b.exclusive {
+                b.value.floor() as i128 + 1
+            } else {
+                b.value.ceil() as i128
+            }
+        });
+        let max = self.max.map(|b| {
+            if b.exclusive {
+                b.value.ceil() as i128 - 1
+            } else {
+                b.value.floor() as i128
+            }
+        });
+        (min, max)
+    }
+
+    /// The inclusive integer bounds worth checking on `rust_type`: ones the type already
+    /// guarantees are dropped, and so are ones it can't even hold (`minimum: 300` on a `u8`).
+    fn integer_checks(&self, rust_type: &str) -> (Option<i128>, Option<i128>) {
+        let (type_min, type_max) = integer_range(rust_type);
+        let (min, max) = self.integer();
+        (
+            min.filter(|min| type_min < *min && *min <= type_max),
+            max.filter(|max| type_min <= *max && *max < type_max),
+        )
+    }
+}
+
+/// Smallest and largest value of an integer type from `integer_type`.
+fn integer_range(rust_type: &str) -> (i128, i128) {
+    match rust_type {
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" => (0, u64::MAX.into()),
+        _ => (i64::MIN.into(), i64::MAX.into()),
+    }
+}
+
+fn is_integer_type(rust_type: &str) -> bool {
+    matches!(
+        rust_type,
+        "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64"
+    )
+}
+
+fn generate_doc_lines(schema: &Value) -> Vec<String> {
+    let mut lines = vec![];
+
+    if let Some(desc) = schema.get("description").and_then(|d| d.as_str()) {
+        lines.push(format!("/// {}", desc));
+    }
+
+    if let Some(example) = schema
+        .get("examples")
+        .and_then(|e| e.as_array())
+        .and_then(|arr| arr.first())
+    {
+        let rendered = match example {
+            Value::String(s) => format!("\"{}\"", s),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            other => other.to_string(),
+        };
+        lines.push(format!("/// Example: {}", rendered));
+    }
+
+    lines
+}
+
+fn doc_lines_to_string_block(schema: &Value, indent: usize) -> String {
+    let prefix = " ".repeat(indent);
+    generate_doc_lines(schema)
+        .into_iter()
+        .map(|line| format!("{}{}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Turns arbitrary text such as an enum value into a PascalCase identifier,
+/// e.g. `in-progress` → `InProgress`, `v1.2` → `V12`, `ACTIVE` → `Active`, `123` → `V123`.
+fn pascal_ident(value: &str) -> Option<String> {
+    let name = value
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = if word.chars().any(|c| c.is_ascii_lowercase()) {
+                word.to_string()
+            } else {
+                word.to_ascii_lowercase()
+            };
+            word[..1].to_ascii_uppercase() + &word[1..]
+        })
+        .collect::<String>();
+
+    match name.chars().next() {
+        None => None,
+        Some(first) if first.is_ascii_digit() => Some(format!("V{}", name)),
+        Some(_) => Some(name),
+    }
+}
+
+/// Variant name for a non-string enum value, e.g. `1` → `V1`, `-1` → `VNeg1`, `true` → `True`.
+fn literal_variant_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => pascal_ident(s),
+        Value::Number(n) => {
+            let text = n.to_string().replace('.', "_");
+            Some(match text.strip_prefix('-') {
+                Some(abs) => format!("VNeg{}", abs),
+                None => format!("V{}", text),
+            })
+        }
+        Value::Bool(b) => Some(if *b { "True" } else { "False" }.to_string()),
+        _ => None,
+    }
+}
+
+/// Picks `name`, or `name2`, `name3`, … if an earlier variant already took it.
+fn unique_variant_name(name: String, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = format!("{}{}", name, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Handles `enum` of literal values.
+///
+/// String enums become a plain serde enum with a `#[serde(rename)]` per variant.
+/// Integer enums convert through `i64`, and mixed literal enums through `serde_json::Value`,
+/// because serde cannot match those by value on its own. `null` makes the field an `Option`.
+pub fn handle_enum(
+    key: &str,
+    prop: &Value,
+    ctx: &mut GeneratorContext,
+    output_path: Option<String>,
+) -> Option<String> {
+    let values = prop.get("enum")?.as_array()?;
+    let fallback = ctx.options.enum_fallback;
+    literal_enum(key, prop, values, fallback, ctx, output_path)
+}
+
+/// Handles `const`, so only that exact value deserializes.
+///
+/// Scalars become a single-variant enum (see `handle_enum`, never with a fallback variant);
+/// objects and arrays become a unit struct converting through `serde_json::Value`.
+pub fn handle_const(
+    key: &str,
+    prop: &Value,
+    ctx: &mut GeneratorContext,
+    output_path: Option<String>,
+) -> Option<String> {
+    let value = prop.get("const")?;
+    if !value.is_array() && !value.is_object() {
+        return literal_enum(
+            key,
+            prop,
+            std::slice::from_ref(value),
+            false,
+            ctx,
+            output_path,
+        );
+    }
+
+    let (name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+    if exists {
+        return Some(name);
+    }
+
+    let code = format!(
+        "#[derive(Debug, Clone, Copy, Serialize, Deserialize)]\n#[serde(try_from = \"serde_json::Value\", into = \"serde_json::Value\")]\npub struct {name};\n\nimpl TryFrom<serde_json::Value> for {name} {{\n    type Error = String;\n\n    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {{\n        if value == serde_json::json!({value}) {{\n            Ok(Self)\n        }} else {{\n            Err(format!(\"invalid {name} value: {{}}\", value))\n        }}\n    }}\n}}\n\nimpl From<{name}> for serde_json::Value {{\n    fn from(_: {name}) -> Self {{\n        serde_json::json!({value})\n    }}\n}}",
+    );
+
+    ctx.literals
+        .insert(name.clone(), LiteralForm::Unit(value.clone()));
+    ctx.output.push(NamedStruct {
+        name: name.clone(),
+        code,
+        output_path,
+        imports: vec![],
+        members: vec![],
+    });
+    Some(name)
+}
+
+/// With `--with-newtypes`, wraps a constrained string or number in a newtype
+/// (`pub struct Email(String)`) whose `TryFrom` — and so its `Deserialize` — rejects
+/// values breaking the schema's `pattern`, `format`, length or range keywords.
+///
+/// Returns `inner` unchanged when the option is off or nothing constrains the value.
+pub fn handle_newtype(
+    key: &str,
+    prop: &Value,
+    inner: &str,
+    ctx: &mut GeneratorContext,
+    output_path: Option<String>,
+) -> String {
+    if !ctx.options.with_newtypes {
+        return inner.to_string();
+    }
+    let (statics, checks) = newtype_checks(prop, inner, ctx);
+    if checks.is_empty() {
+        return inner.to_string();
+    }
+
+    let (name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+    if exists {
+        return name;
+    }
+
+    let body = statics
+        .iter()
+        .map(|line| format!("        {}\n", line))
+        .chain(checks.iter().map(|(violated, message)| {
+            // The message becomes a format string: braces in a pattern must not be read as args
+            let message = format!("{} {}", name, message)
+                .replace('{', "{{")
+                .replace('}', "}}");
+            format!(
+                "        if {} {{\n            return Err(format!({:?}, value));\n        }}\n",
+                violated,
+                format!("{}, got {{:?}}", message)
+            )
+        }))
+        .collect::<String>();
+    let code = format!(
+        "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = \"{inner}\")]
+pub struct {name}({inner});
+
+impl TryFrom<{inner}> for {name} {{
+    type Error = String;
+
+    fn try_from(value: {inner}) -> Result<Self, Self::Error> {{
+{body}        Ok(Self(value))
+    }}
+}}
+
+impl std::ops::Deref for {name} {{
+    type Target = {inner};
+
+    fn deref(&self) -> &{inner} {{
+        &self.0
+    }}
+}}"
+    );
+
+    ctx.output.push(NamedStruct {
+        name: name.clone(),
+        code,
+        output_path,
+        imports: vec![],
+        members: vec![inner.to_string()],
+    });
+    name
+}
+
+/// `(violated, message)` checks for a newtype over `inner`, plus the statics they use.
+///
+/// Integer bounds the type already guarantees (`minimum: 0` on a `u8`) are left out.
+fn newtype_checks(
+    prop: &Value,
+    inner: &str,
+    ctx: &mut GeneratorContext,
+) -> (Vec<String>, Vec<(String, String)>) {
+    let mut statics = vec![];
+    let mut checks = vec![];
+
+    if inner == "String" {
+        if let Some(n) = prop.get("minLength").and_then(|n| n.as_u64()) {
+            checks.push((
+                format!("value.chars().count() < {}", n),
+                format!("must be at least {} characters", n),
+            ));
+        }
+        if let Some(n) = prop.get("maxLength").and_then(|n| n.as_u64()) {
+            checks.push((
+                format!("value.chars().count() > {}", n),
+                format!("must be at most {} characters", n),
+            ));
+        }
+        if let Some(pattern) = prop.get("pattern").and_then(|p| p.as_str()) {
+            let lazy_lock = ctx.use_type("std::sync::LazyLock");
+            let regex = ctx.use_type("regex::Regex");
+            statics.push(format!(
+                "static PATTERN: {}<{}> = {}::new(|| {}::new({:?}).unwrap());",
+                lazy_lock, regex, lazy_lock, regex, pattern
+            ));
+            checks.push((
+                "!PATTERN.is_match(&value)".to_string(),
+                format!("must match {}", pattern),
+            ));
+        }
+        match prop.get("format").and_then(|f| f.as_str()) {
+            Some("email") => checks.push((
+                "!value.split_once('@').is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))"
+                    .to_string(),
+                "must be an email address".to_string(),
+            )),
+            Some("uri") | Some("url") => checks.push((
+                "!value.split_once(':').is_some_and(|(scheme, _)| !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || \"+-.\".contains(c)))"
+                    .to_string(),
+                "must be a URI".to_string(),
+            )),
+            _ => {}
+        }
+        return (statics, checks);
+    }
+
+    let bounds = NumberBounds::of(prop);
+    let number = |key: &str| prop.get(key).and_then(|n| n.as_f64());
+
+    if is_integer_type(inner) {
+        let (min, max) = bounds.integer_checks(inner);
+        if let Some(min) = min {
+            checks.push((
+                format!("value < {}", min),
+                format!("must be at least {}", min),
+            ));
+        }
+        if let Some(max) = max {
+            checks.push((
+                format!("value > {}", max),
+                format!("must be at most {}", max),
+            ));
+        }
+        let (_, type_max) = integer_range(inner);
+        if let Some(step) = number("multipleOf")
+            .filter(|step| step.fract() == 0.0 && *step > 1.0 && *step <= type_max as f64)
+        {
+            checks.push((
+                format!("value % {} != 0", step as i128),
+                format!("must be a multiple of {}", step as i128),
+            ));
+        }
+        return (statics, checks);
+    }
+
+    match bounds.min {
+        Some(Bound {
+            value,
+            exclusive: true,
+        }) => checks.push((
+            format!("value <= {:?}", value),
+            format!("must be greater than {}", value),
+        )),
+        Some(Bound { value, .. }) => checks.push((
+            format!("value < {:?}", value),
+            format!("must be at least {}", value),
+        )),
+        None => {}
+    }
+    match bounds.max {
+        Some(Bound {
+            value,
+            exclusive: true,
+        }) => checks.push((
+            format!("value >= {:?}", value),
+            format!("must be less than {}", value),
+        )),
+        Some(Bound { value, .. }) => checks.push((
+            format!("value > {:?}", value),
+            format!("must be at most {}", value),
+        )),
+        None => {}
+    }
+    if let Some(step) = number("multipleOf") {
+        checks.push((
+            format!("(value / {:?}).fract() != 0.0", step),
+            format!("must be a multiple of {}", step),
+        ));
+    }
+    (statics, checks)
+}
+
+/// Generates the enum for `handle_enum`/`handle_const` from its literal `values`.
+fn literal_enum(
+    key: &str,
+    prop: &Value,
+    values: &[Value],
+    fallback: bool,
+    ctx: &mut GeneratorContext,
+    output_path: Option<String>,
+) -> Option<String> {
+    let nullable = values.iter().any(|v| v.is_null());
+    let values = values.iter().filter(|v| !v.is_null()).collect::<Vec<_>>();
+
+    if values.is_empty() || values.iter().any(|v| v.is_array() || v.is_object()) {
+        return Some("serde_json::Value".to_string());
+    }
+
+    let (enum_name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+    if exists {
+        return Some(if nullable {
+            format!("Option<{}>", enum_name)
+        } else {
+            enum_name
+        });
+    }
+
+    let var_names = prop
+        .get("x-enum-varnames")
+        .and_then(|n| n.as_array())
+        .filter(|names| names.len() == values.len());
+
+    let mut taken = HashSet::new();
+    let variants = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let name = var_names
+                .and_then(|names| names[i].as_str())
+                .and_then(pascal_ident)
+                .or_else(|| literal_variant_name(value))
+                .unwrap_or_else(|| format!("Variant{}", i + 1));
+            (unique_variant_name(name, &mut taken), *value)
+        })
+        .collect::<Vec<_>>();
+
+    let fallback = fallback.then(|| {
+        let name = if taken.contains("Unknown") {
+            "Other"
+        } else {
+            "Unknown"
+        };
+        unique_variant_name(name.to_string(), &mut taken)
+    });
+
+    ctx.literals.insert(
+        enum_name.clone(),
+        LiteralForm::Enum(
+            variants
+                .iter()
+                .map(|(name, value)| ((*value).clone(), name.clone()))
+                .collect(),
+        ),
+    );
+
+    let (code, members) = if variants.iter().all(|(_, v)| v.is_string()) {
+        let mut lines = vec![];
+        for (name, value) in &variants {
+            let wire = value.as_str().unwrap_or_default();
+            if name != wire {
+                lines.push(format!("    #[serde(rename = {:?})]", wire));
+            }
+            lines.push(format!("    {},", name));
+        }
+        if let Some(fallback) = &fallback {
+            lines.push(format!("    #[serde(other)]\n    {},", fallback));
+        }
+        let code = format!(
+            "#[derive(Debug, Serialize, Deserialize)]\npub enum {} {{\n{}\n}}",
+            enum_name,
+            lines.join("\n")
+        );
+        (code, vec![])
+    } else if variants.iter().all(|(_, v)| v.is_i64()) {
+        let mut decls = variants
+            .iter()
+            .map(|(name, _)| format!("    {},", name))
+            .collect::<Vec<_>>();
+        let mut from_wire = variants
+            .iter()
+            .map(|(name, v)| format!("            {} => Ok(Self::{}),", v, name))
+            .collect::<Vec<_>>();
+        let mut to_wire = variants
+            .iter()
+            .map(|(name, v)| format!("            {}::{} => {},", enum_name, name, v))
+            .collect::<Vec<_>>();
+        match &fallback {
+            Some(fallback) => {
+                decls.push(format!("    {}(i64),", fallback));
+                from_wire.push(format!(
+                    "            other => Ok(Self::{}(other)),",
+                    fallback
+                ));
+                to_wire.push(format!(
+                    "            {}::{}(other) => other,",
+                    enum_name, fallback
+                ));
+            }
+            None => from_wire.push(format!(
+                "            other => Err(format!(\"invalid {} value: {{}}\", other)),",
+                enum_name
+            )),
+        }
+
+        let code = format!(
+            "#[derive(Debug, Clone, Copy, Serialize, Deserialize)]\n#[serde(try_from = \"i64\", into = \"i64\")]\npub enum {name} {{\n{decls}\n}}\n\nimpl TryFrom<i64> for {name} {{\n    type Error = String;\n\n    fn try_from(value: i64) -> Result<Self, Self::Error> {{\n        match value {{\n{from_wire}\n        }}\n    }}\n}}\n\nimpl From<{name}> for i64 {{\n    fn from(value: {name}) -> Self {{\n        match value {{\n{to_wire}\n        }}\n    }}\n}}",
+            name = enum_name,
+            decls = decls.join("\n"),
+            from_wire = from_wire.join("\n"),
+            to_wire = to_wire.join("\n"),
+        );
+        let members = fallback.iter().map(|_| "i64".to_string()).collect();
+        (code, members)
+    } else {
+        let mut decls = variants
+            .iter()
+            .map(|(name, _)| format!("    {},", name))
+            .collect::<Vec<_>>();
+        let mut from_wire = variants
+            .iter()
+            .map(|(name, v)| {
+                let test = match v {
+                    Value::String(s) => format!("value.as_str() == Some({:?})", s),
+                    Value::Bool(b) => format!("value.as_bool() == Some({})", b),
+                    Value::Number(n) if n.is_i64() => format!("value.as_i64() == Some({})", n),
+                    Value::Number(n) if n.is_u64() => format!("value.as_u64() == Some({})", n),
+                    Value::Number(n) => format!(
+                        "value.as_f64() == Some({:?})",
+                        n.as_f64().unwrap_or_default()
+                    ),
+                    _ => "false".to_string(),
+                };
+                format!(
+                    "        if {} {{\n            return Ok(Self::{});\n        }}",
+                    test, name
                 )
-                .unwrap_or_else(|| "serde_json::Value".to_string());
-                return Some(format!("Option<HashMap<String, {}>>", inner_type));
+            })
+            .collect::<Vec<_>>();
+        let mut to_wire = variants
+            .iter()
+            .map(|(name, v)| {
+                format!(
+                    "            {}::{} => serde_json::Value::from({}),",
+                    enum_name,
+                    name,
+                    match v {
+                        Value::String(s) => format!("{:?}", s),
+                        Value::Number(n) if n.is_f64() =>
+                            format!("{:?}", n.as_f64().unwrap_or_default()),
+                        other => other.to_string(),
+                    }
+                )
+            })
+            .collect::<Vec<_>>();
+        match &fallback {
+            Some(fallback) => {
+                decls.push(format!("    {}(serde_json::Value),", fallback));
+                from_wire.push(format!("        Ok(Self::{}(value))", fallback));
+                to_wire.push(format!(
+                    "            {}::{}(other) => other,",
+                    enum_name, fallback
+                ));
             }
-            if prop.get("properties").is_some() {
-                let sub_name = to_pascal_case(key);
-                extract_struct_recursive(
-                    &sub_name,
-                    prop,
-                    ctx,
-                    "#".to_string(),
-                    definitions,
-                    resolver,
-                    output_path.clone(),
-                    with_docs,
-                );
-                return Some(sub_name);
+            None => from_wire.push(format!(
+                "        Err(format!(\"invalid {} value: {{}}\", value))",
+                enum_name
+            )),
+        }
+
+        let code = format!(
+            "#[derive(Debug, Clone, Serialize, Deserialize)]\n#[serde(try_from = \"serde_json::Value\", into = \"serde_json::Value\")]\npub enum {name} {{\n{decls}\n}}\n\nimpl TryFrom<serde_json::Value> for {name} {{\n    type Error = String;\n\n    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {{\n{from_wire}\n    }}\n}}\n\nimpl From<{name}> for serde_json::Value {{\n    fn from(value: {name}) -> Self {{\n        match value {{\n{to_wire}\n        }}\n    }}\n}}",
+            name = enum_name,
+            decls = decls.join("\n"),
+            from_wire = from_wire.join("\n"),
+            to_wire = to_wire.join("\n"),
+        );
+        let members = fallback
+            .iter()
+            .map(|_| "serde_json::Value".to_string())
+            .collect();
+        (code, members)
+    };
+
+    ctx.output.push(NamedStruct {
+        name: enum_name.clone(),
+        code,
+        output_path,
+        imports: vec![],
+        members,
+    });
+
+    Some(if nullable {
+        format!("Option<{}>", enum_name)
+    } else {
+        enum_name
+    })
+}
+
+/// Handles `oneOf`.
+///
+/// When every variant is an object sharing a discriminating property, named by OpenAPI's
+/// `discriminator.propertyName` or found as a distinct `const`/single-value `enum`, this
+/// emits an internally tagged enum with each variant renamed to its tag value. Otherwise
+/// it falls back to an untagged enum and warns.
+pub fn handle_one_of(
+    key: &str,
+    prop: &Value,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) -> Option<String> {
+    let one_of = prop.get("oneOf")?.as_array()?;
+    let (enum_name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+    if exists {
+        return Some(ctx.box_if_recursive(&enum_name));
+    }
+
+    let resolved = one_of
+        .iter()
+        .map(|variant| deref_schema(variant, document, &ctx.base, resolver))
+        .collect::<Option<Vec<_>>>();
+    let discriminator = resolved
+        .as_ref()
+        .and_then(|resolved| find_discriminator(prop, one_of, resolved));
+
+    let (Some(resolved), Some((tag, tag_values))) = (resolved, discriminator) else {
+        log_warn!(
+            "⚠️ oneOf `{}` has no discriminator property, generating an untagged enum",
+            enum_name
+        );
+        let variants = one_of
+            .iter()
+            .enumerate()
+            .map(|(i, variant)| {
+                let var_name =
+                    one_of_variant_name(variant).unwrap_or_else(|| format!("Variant{}", i + 1));
+                let var_key = format!("{}{}", enum_name, var_name);
+                (var_name, var_key, variant.clone())
+            })
+            .collect::<Vec<_>>();
+        return emit_untagged_enum(
+            &enum_name,
+            &variants,
+            ctx,
+            document,
+            resolver,
+            output_path,
+            with_docs,
+        );
+    };
+
+    let mut variants = vec![];
+    let mut members = vec![];
+    let mut taken = HashSet::new();
+    ctx.in_progress.push(enum_name.clone());
+
+    for (i, ((variant, (schema, variant_document, base)), tag_value)) in
+        one_of.iter().zip(resolved).zip(tag_values).enumerate()
+    {
+        let var_name = one_of_variant_name(variant)
+            .or_else(|| pascal_ident(&tag_value))
+            .unwrap_or_else(|| format!("Variant{}", i + 1));
+        let var_name = unique_variant_name(var_name, &mut taken);
+
+        // The enum reads and writes the tag itself, so the payload struct must not
+        let payload = without_property(&schema, &tag);
+        let (struct_name, exists) =
+            ctx.claim_type_name(&format!("{}{}", enum_name, var_name), &payload);
+        if !exists {
+            let outer_base = std::mem::replace(&mut ctx.base, base);
+            extract_struct_recursive(
+                &struct_name,
+                &payload,
+                ctx,
+                "#".to_string(),
+                &variant_document,
+                resolver,
+                output_path.clone(),
+                with_docs,
+            );
+            ctx.base = outer_base;
+        }
+
+        if var_name != tag_value {
+            variants.push(format!("    #[serde(rename = {:?})]", tag_value));
+        }
+        if with_docs {
+            let doc_block = doc_lines_to_string_block(&schema, 4);
+            if !doc_block.is_empty() {
+                variants.push(doc_block);
             }
-            Some("serde_json::Value".to_string())
         }
-        _ => Some("serde_json::Value".to_string()),
+        variants.push(format!("    {}({}),", var_name, struct_name));
+        members.push(struct_name);
     }
+
+    let code = format!(
+        "#[derive(Debug, Serialize, Deserialize)]\n#[serde(tag = {:?})]\npub enum {} {{\n{}\n}}",
+        tag,
+        enum_name,
+        variants.join("\n")
+    );
+
+    ctx.in_progress.pop();
+    ctx.output.push(NamedStruct {
+        name: enum_name.clone(),
+        code,
+        output_path,
+        imports: vec![],
+        members,
+    });
+    Some(enum_name)
 }
 
-fn generate_default_function(field_name: &str, prop: &Value) -> Option<String> {
-    if prop.get("default").is_some() || prop.get("const").is_some() {
-        Some(format!("default_{}", to_snake_case(field_name)))
-    } else {
-        None
+/// Finds the property that tells `oneOf` variants apart, with each variant's tag value.
+///
+/// OpenAPI's `discriminator.propertyName` wins; its values come from the variant's
+/// `const`/`enum`, then `discriminator.mapping`, then the referenced schema's name.
+/// Without it, the first property every variant pins to a distinct string is used.
+fn find_discriminator(
+    prop: &Value,
+    one_of: &[Value],
+    resolved: &[(Value, Value, SchemaBase)],
+) -> Option<(String, Vec<String>)> {
+    if resolved
+        .iter()
+        .any(|(schema, _, _)| schema.get("properties").is_none())
+    {
+        return None;
     }
-}
 
-fn default_fn_code_string(field_name: &str, prop: &Value) -> String {
-    let fn_name = format!("default_{}", to_snake_case(field_name));
-    let val = prop.get("default").or_else(|| prop.get("const"));
+    let distinct = |values: &[String]| values.iter().collect::<HashSet<_>>().len() == values.len();
 
-    let rendered = if let Some(v) = val {
-        match v {
-            Value::String(s) => format!("\"{}\".to_string()", s),
-            Value::Number(n) => n.to_string(),
-            Value::Bool(b) => b.to_string(),
-            _ => "Default::default()".to_string(),
-        }
-    } else {
-        "Default::default()".to_string()
+    if let Some(tag) = prop
+        .pointer("/discriminator/propertyName")
+        .and_then(|t| t.as_str())
+    {
+        let mapping = prop
+            .pointer("/discriminator/mapping")
+            .and_then(|m| m.as_object());
+        let values = one_of
+            .iter()
+            .zip(resolved)
+            .map(|(variant, (schema, _, _))| {
+                pinned_string(schema, tag).or_else(|| {
+                    let ref_val = variant.get("$ref")?.as_str()?;
+                    mapping
+                        .and_then(|mapping| {
+                            mapping
+                                .iter()
+                                .find(|(_, target)| target.as_str() == Some(ref_val))
+                                .map(|(value, _)| value.clone())
+                        })
+                        .or_else(|| ref_val.rsplit(['/', '#']).next().map(str::to_string))
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        return distinct(&values).then(|| (tag.to_string(), values));
+    }
+
+    let (first, _, _) = resolved.first()?;
+    let mut candidates = first
+        .get("properties")?
+        .as_object()?
+        .keys()
+        .collect::<Vec<_>>();
+    // Conventional tag names first, then alphabetical order
+    candidates.sort_by_key(|name| !matches!(name.as_str(), "type" | "kind"));
+
+    candidates.into_iter().find_map(|tag| {
+        let values = resolved
+            .iter()
+            .map(|(schema, _, _)| pinned_string(schema, tag))
+            .collect::<Option<Vec<_>>>()?;
+        distinct(&values).then(|| (tag.clone(), values))
+    })
+}
+
+/// The single string `schema` allows for `property`, via `const` or a one-value `enum`.
+fn pinned_string(schema: &Value, property: &str) -> Option<String> {
+    let prop = schema.get("properties")?.get(property)?;
+    let value = match prop.get("enum").and_then(|e| e.as_array()) {
+        Some(values) if values.len() == 1 => &values[0],
+        _ => prop.get("const")?,
     };
+    value.as_str().map(str::to_string)
+}
 
-    format!(
-        "fn {}() -> {} {{\n    {}\n}}",
-        fn_name,
-        rust_type_for_value(val.unwrap_or(&Value::Null)),
-        rendered
-    )
+/// A copy of `schema` without `property` in its `properties` and `required`.
+fn without_property(schema: &Value, property: &str) -> Value {
+    let mut schema = schema.clone();
+    if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        properties.remove(property);
+    }
+    if let Some(required) = schema.get_mut("required").and_then(|r| r.as_array_mut()) {
+        required.retain(|r| r != property);
+    }
+    schema
 }
 
-fn rust_type_for_value(v: &Value) -> &str {
-    match v {
-        Value::String(_) => "String",
-        Value::Number(n) => {
-            if n.is_i64() {
-                "i32"
-            } else {
-                "f64"
-            }
+/// Variant name from a `oneOf` entry's `title`, or the name of the schema it references.
+fn one_of_variant_name(variant: &Value) -> Option<String> {
+    if let Some(title) = variant.get("title").and_then(|t| t.as_str()) {
+        return Some(to_pascal_case(title));
+    }
+    let ref_val = variant.get("$ref")?.as_str()?;
+    match ref_val.split_once('#') {
+        Some((_, fragment)) if !fragment.is_empty() => {
+            type_name_for_fragment(&percent_decode(fragment))
         }
-        Value::Bool(_) => "bool",
-        _ => "&'static str",
+        _ => Some(to_pascal_case(
+            Path::new(ref_val.split('#').next()?)
+                .file_stem()?
+                .to_str()?,
+        )),
     }
 }
 
-fn generate_doc_lines(schema: &Value) -> Vec<String> {
-    let mut lines = vec![];
+/// Follows `$ref`s from `schema` to a concrete schema, returning it together with
+/// the document and base its own refs resolve against.
+fn deref_schema(
+    schema: &Value,
+    document: &Value,
+    base: &SchemaBase,
+    resolver: &mut RefResolver,
+) -> Option<(Value, Value, SchemaBase)> {
+    let (mut schema, mut document, mut base) = (schema.clone(), document.clone(), base.clone());
 
-    if let Some(desc) = schema.get("description").and_then(|d| d.as_str()) {
-        lines.push(format!("/// {}", desc));
+    // Bounded so a ref cycle without any schema in between can't loop forever
+    for _ in 0..32 {
+        let Some(ref_val) = schema
+            .get("$ref")
+            .and_then(|r| r.as_str())
+            .map(str::to_string)
+        else {
+            return Some((schema, document, base));
+        };
+        let (file_path, fragment) = ref_val.split_once('#').unwrap_or((&ref_val, ""));
+        if !file_path.is_empty() {
+            let ResolvedRef {
+                resource,
+                base: ref_base,
+            } = resolver.resolve(file_path, &base)?;
+            document = resource;
+            base = ref_base;
+        }
+        schema = resolve_fragment(&document, &percent_decode(fragment))?.clone();
     }
+    None
+}
 
-    if let Some(example) = schema
-        .get("examples")
-        .and_then(|e| e.as_array())
-        .and_then(|arr| arr.get(0))
-    {
-        let rendered = match example {
-            Value::String(s) => format!("\"{}\"", s),
-            Value::Number(n) => n.to_string(),
-            Value::Bool(b) => b.to_string(),
-            other => other.to_string(),
-        };
-        lines.push(format!("/// Example: {}", rendered));
+pub fn handle_any_of(
+    key: &str,
+    prop: &Value,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) -> Option<String> {
+    let any_of = prop.get("anyOf")?.as_array()?;
+    let (enum_name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+    if exists {
+        return Some(ctx.box_if_recursive(&enum_name));
     }
 
-    lines
-}
+    let variants = any_of
+        .iter()
+        .enumerate()
+        .map(|(i, variant)| {
+            let var_name = format!("Variant{}", i + 1);
+            (var_name.clone(), var_name, variant.clone())
+        })
+        .collect::<Vec<_>>();
 
-fn doc_lines_to_string_block(schema: &Value, indent: usize) -> String {
-    let prefix = " ".repeat(indent);
-    generate_doc_lines(schema)
-        .into_iter()
-        .map(|line| format!("{}{}", prefix, line))
-        .collect::<Vec<_>>()
-        .join("\n")
+    emit_untagged_enum(
+        &enum_name,
+        &variants,
+        ctx,
+        document,
+        resolver,
+        output_path,
+        with_docs,
+    )
 }
 
-pub fn handle_one_of(
+/// Handles `type: [T1, T2, ...]`.
+///
+/// A single non-null type plus `"null"` becomes `Option<T>`; several non-null types
+/// become an untagged enum named after the property, with one variant per type.
+pub fn handle_multi_type(
     key: &str,
-    one_of: &Value,
+    prop: &Value,
     ctx: &mut GeneratorContext,
-    definitions: &Value,
+    document: &Value,
     resolver: &mut RefResolver,
     output_path: Option<String>,
     with_docs: bool,
 ) -> Option<String> {
-    let enum_name = to_pascal_case(key);
-    let mut variants = vec![];
+    let types = prop.get("type")?.as_array()?;
+    let nullable = types.iter().any(|t| t == "null");
+    let mut non_null = types
+        .iter()
+        .filter_map(|t| t.as_str())
+        .filter(|t| *t != "null")
+        .collect::<Vec<_>>();
+    // untagged enums try variants in order, so integers must be tried before numbers
+    non_null.sort_by_key(|t| *t == "number");
 
-    for variant in one_of.as_array()? {
-        let title = variant
-            .get("title")
-            .and_then(|t| t.as_str())
-            .map(|s| to_pascal_case(s))
-            .unwrap_or_else(|| format!("Variant{}", variants.len() + 1));
-
-        let struct_name = format!("{}{}", enum_name, &title);
+    // Narrow the schema to one type; docs stay on the field, not on each variant
+    let with_single_type = |t: &str| {
+        let mut single = prop.clone();
+        if let Some(obj) = single.as_object_mut() {
+            obj.insert("type".to_string(), Value::String(t.to_string()));
+            obj.remove("description");
+            obj.remove("examples");
+        }
+        single
+    };
 
-        extract_struct_recursive(
-            &struct_name,
-            variant,
+    let inner = match non_null.as_slice() {
+        [] => "serde_json::Value".to_string(),
+        [single] => infer_rust_type(
+            &with_single_type(single),
+            key,
             ctx,
-            "#".to_string(),
-            definitions,
+            document,
             resolver,
-            output_path.clone(),
+            output_path,
             with_docs,
-        );
-
-        if with_docs {
-            let doc_block = doc_lines_to_string_block(variant, 4);
-            if !doc_block.is_empty() {
-                variants.push(doc_block);
+        )?,
+        many => {
+            let (enum_name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+            if exists {
+                return Some(if nullable {
+                    format!("Option<{}>", enum_name)
+                } else {
+                    ctx.box_if_recursive(&enum_name)
+                });
             }
-            variants.push(format!("    {}({}),", title, struct_name));
-        } else {
-            variants.push(format!("    {}({}),", title, struct_name));
+            let variants = many
+                .iter()
+                .map(|t| {
+                    let var_name = to_pascal_case(t);
+                    let var_key = format!("{}{}", key, var_name);
+                    (var_name, var_key, with_single_type(t))
+                })
+                .collect::<Vec<_>>();
+            emit_untagged_enum(
+                &enum_name,
+                &variants,
+                ctx,
+                document,
+                resolver,
+                output_path,
+                with_docs,
+            )?
         }
+    };
+
+    Some(if nullable && !inner.starts_with("Option<") {
+        format!("Option<{}>", inner)
+    } else {
+        inner
+    })
+}
+
+/// Handles fixed-position arrays: 2020-12 `prefixItems` or draft-04/07 array-form `items`.
+///
+/// Without a schema for the remaining items this is a plain tuple such as `(f64, f64)`;
+/// with one (`items` after `prefixItems`, or `additionalItems`) it becomes a tuple struct
+/// whose last field collects the rest, serialized back as one flat array.
+pub fn handle_tuple(
+    key: &str,
+    prop: &Value,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) -> Option<String> {
+    let (positional, rest) = match prop.get("prefixItems") {
+        Some(prefix) => (prefix.as_array()?, prop.get("items")),
+        None => (prop.get("items")?.as_array()?, prop.get("additionalItems")),
+    };
+
+    let mut element_types = vec![];
+    for (i, item) in positional.iter().enumerate() {
+        element_types.push(
+            infer_rust_type(
+                item,
+                &format!("{}Item{}", key, i + 1),
+                ctx,
+                document,
+                resolver,
+                output_path.clone(),
+                with_docs,
+            )
+            .unwrap_or_else(|| "serde_json::Value".to_string()),
+        );
     }
 
-    let mut lines = vec![];
-    lines.push(format!(
-        "#[derive(Debug, Serialize, Deserialize)]\n#[serde(tag = \"type\")]\npub enum {} {{\n{}\n}}",
-        enum_name,
-        variants.join("\n")
-    ));
+    let Some(rest) = rest.filter(|rest| rest.is_object()) else {
+        return Some(match element_types.as_slice() {
+            [single] => format!("({},)", single),
+            _ => format!("({})", element_types.join(", ")),
+        });
+    };
+
+    let (name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+    if exists {
+        return Some(ctx.box_if_recursive(&name));
+    }
+    let rest_type = infer_rust_type(
+        rest,
+        &format!("{}Rest", key),
+        ctx,
+        document,
+        resolver,
+        output_path.clone(),
+        with_docs,
+    )
+    .map(unbox)
+    .unwrap_or_else(|| "serde_json::Value".to_string());
+
+    let count = element_types.len();
+    let fields = element_types
+        .iter()
+        .map(|t| format!("pub {}", t))
+        .chain(std::iter::once(format!("pub Vec<{}>", rest_type)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let serialize_items = (0..count)
+        .map(|i| format!("        seq.serialize_element(&self.{})?;", i))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let deserialize_items = (0..count)
+        .map(|i| {
+            format!(
+                "                let item_{i} = seq\n                    .next_element()?\n                    .ok_or_else(|| serde::de::Error::invalid_length({i}, &self))?;"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let item_args = (0..count)
+        .map(|i| format!("item_{}, ", i))
+        .collect::<String>();
+
+    let code = format!(
+        "#[derive(Debug)]
+pub struct {name}({fields});
+
+impl Serialize for {name} {{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(Some({count} + self.{count}.len()))?;
+{serialize_items}
+        for item in &self.{count} {{
+            seq.serialize_element(item)?;
+        }}
+        seq.end()
+    }}
+}}
+
+impl<'de> Deserialize<'de> for {name} {{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        struct Visitor;
 
-    let code = lines.join("\n");
+        impl<'de> serde::de::Visitor<'de> for Visitor {{
+            type Value = {name};
 
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {{
+                f.write_str(\"an array with at least {count} items\")
+            }}
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<{name}, A::Error> {{
+{deserialize_items}
+                let mut rest = Vec::new();
+                while let Some(item) = seq.next_element()? {{
+                    rest.push(item);
+                }}
+                Ok({name}({item_args}rest))
+            }}
+        }}
+
+        deserializer.deserialize_seq(Visitor)
+    }}
+}}"
+    );
+
+    let mut members = element_types;
+    members.push(format!("Vec<{}>", rest_type));
     ctx.output.push(NamedStruct {
-        name: enum_name.clone(),
+        name: name.clone(),
         code,
         output_path,
+        imports: vec![],
+        members,
     });
-    Some(enum_name)
+    Some(name)
 }
 
-pub fn handle_any_of(
-    key: &str,
-    any_of: &Value,
+/// Emits `#[serde(untagged)]` enum from `(variant name, inference key, schema)` triples.
+fn emit_untagged_enum(
+    enum_name: &str,
+    variants: &[(String, String, Value)],
     ctx: &mut GeneratorContext,
-    definitions: &Value,
+    document: &Value,
     resolver: &mut RefResolver,
     output_path: Option<String>,
     with_docs: bool,
 ) -> Option<String> {
-    let enum_name = to_pascal_case(key);
-    let mut variants = vec![];
-
-    for (i, variant) in any_of.as_array()?.iter().enumerate() {
-        let var_name = format!("Variant{}", i + 1);
+    let mut variant_lines = vec![];
+    let mut members = vec![];
+    ctx.in_progress.push(enum_name.to_string());
 
+    for (var_name, var_key, variant) in variants {
         let inner_type = infer_rust_type(
             variant,
-            &var_name,
+            var_key,
             ctx,
-            definitions,
+            document,
             resolver,
             output_path.clone(),
             with_docs,
@@ -766,45 +4166,69 @@ pub fn handle_any_of(
         if with_docs {
             let doc_block = doc_lines_to_string_block(variant, 4);
             if !doc_block.is_empty() {
-                variants.push(doc_block);
+                variant_lines.push(doc_block);
             }
-            variants.push(format!("    {}({}),", var_name, inner_type));
-        } else {
-            variants.push(format!("    {}({}),", var_name, inner_type));
         }
+        variant_lines.push(format!("    {}({}),", var_name, inner_type));
+        members.push(inner_type);
     }
 
-    let mut lines = vec![];
-    lines.push(format!(
+    let code = format!(
         "#[derive(Debug, Serialize, Deserialize)]\n#[serde(untagged)]\npub enum {} {{\n{}\n}}",
         enum_name,
-        variants.join("\n")
-    ));
-
-    let code = lines.join("\n");
+        variant_lines.join("\n")
+    );
 
+    ctx.in_progress.pop();
     ctx.output.push(NamedStruct {
-        name: enum_name.clone(),
+        name: enum_name.to_string(),
         code,
         output_path,
+        imports: vec![],
+        members,
     });
-    Some(enum_name)
+    Some(enum_name.to_string())
 }
 
 pub fn handle_all_of(
     key: &str,
-    all_of: &Value,
+    prop: &Value,
     ctx: &mut GeneratorContext,
-    definitions: &Value,
+    document: &Value,
     resolver: &mut RefResolver,
     output_path: Option<String>,
     with_docs: bool,
 ) -> Option<String> {
-    let main_struct_name = to_pascal_case(key);
+    let all_of = prop.get("allOf")?.as_array()?;
+    let (main_struct_name, exists) = ctx.claim_type_name(&to_pascal_case(key), prop);
+    if exists {
+        return Some(ctx.box_if_recursive(&main_struct_name));
+    }
+    if ctx.options.merge_all_of {
+        return merge_all_of(
+            &main_struct_name,
+            prop,
+            ctx,
+            document,
+            resolver,
+            output_path,
+            with_docs,
+        );
+    }
     let mut field_lines = vec![];
+    ctx.in_progress.push(main_struct_name.clone());
+    ctx.structs.insert(main_struct_name.clone());
 
-    for (i, schema_part) in all_of.as_array()?.iter().enumerate() {
-        let part_name = format!("{}Part{}", main_struct_name, i + 1);
+    for (i, schema_part) in all_of.iter().enumerate() {
+        // Each part sees the other parts' keys once flattened, so none may reject them
+        let mut schema_part = schema_part.clone();
+        if let Some(part) = schema_part.as_object_mut() {
+            part.remove("additionalProperties");
+            part.remove("unevaluatedProperties");
+        }
+        let schema_part = &schema_part;
+        let (part_name, exists) =
+            ctx.claim_type_name(&format!("{}Part{}", main_struct_name, i + 1), schema_part);
 
         if with_docs {
             let doc_block = doc_lines_to_string_block(schema_part, 4);
@@ -812,17 +4236,22 @@ pub fn handle_all_of(
                 field_lines.push(doc_block);
             }
         }
-        extract_struct_recursive(
-            &part_name,
-            schema_part,
-            ctx,
-            "#".to_string(),
-            definitions,
-            resolver,
-            output_path.clone(),
-            with_docs,
-        );
+        if !exists {
+            extract_struct_recursive(
+                &part_name,
+                schema_part,
+                ctx,
+                "#".to_string(),
+                document,
+                resolver,
+                output_path.clone(),
+                with_docs,
+            );
+        }
 
+        if ctx.options.with_validation {
+            field_lines.push("    #[validate(nested)]".to_string());
+        }
         field_lines.push(format!(
             "    #[serde(flatten)]\n    pub part_{}: {},",
             i + 1,
@@ -830,9 +4259,16 @@ pub fn handle_all_of(
         ));
     }
 
+    ctx.in_progress.pop();
+    let derive = if ctx.options.with_validation {
+        "#[derive(Debug, Serialize, Deserialize, Validate)]"
+    } else {
+        "#[derive(Debug, Serialize, Deserialize)]"
+    };
     let mut struct_lines = vec![];
     struct_lines.push(format!(
-        "#[derive(Debug, Serialize, Deserialize)]\npub struct {} {{\n{}\n}}",
+        "{}\npub struct {} {{\n{}\n}}",
+        derive,
         main_struct_name,
         field_lines.join("\n")
     ));
@@ -843,10 +4279,174 @@ pub fn handle_all_of(
         name: main_struct_name.clone(),
         code,
         output_path,
+        imports: vec![],
+        members: field_types(&field_lines),
     });
     Some(main_struct_name)
 }
 
+/// `--merge-all-of`: combines the `properties` and `required` of every part (following
+/// `$ref`s and nested `allOf`s) into one struct, warning when parts disagree on a type.
+fn merge_all_of(
+    name: &str,
+    prop: &Value,
+    ctx: &mut GeneratorContext,
+    document: &Value,
+    resolver: &mut RefResolver,
+    output_path: Option<String>,
+    with_docs: bool,
+) -> Option<String> {
+    let mut parts = vec![];
+    collect_all_of_parts(prop, document, &ctx.base.clone(), resolver, &mut parts)?;
+
+    let mut merged = serde_json::Map::new();
+    let mut properties = serde_json::Map::new();
+    let mut required: Vec<Value> = vec![];
+
+    for (i, (part, base)) in parts.into_iter().enumerate() {
+        // Refs in a part from another file must keep pointing into that file
+        let part = if base.file != ctx.base.file {
+            rebase_refs(&part, &base)
+        } else {
+            part
+        };
+        let Value::Object(part) = part else {
+            continue;
+        };
+
+        for (keyword, value) in part {
+            match (keyword.as_str(), value) {
+                ("properties", Value::Object(part_properties)) => {
+                    for (key, schema) in part_properties {
+                        let Some(existing) = properties.get_mut(&key) else {
+                            properties.insert(key, schema);
+                            continue;
+                        };
+                        if let Some((kept, dropped)) = merge_property_schema(existing, &schema) {
+                            log_warn!(
+                                "⚠️ allOf `{}`: property `{}` is {} in one part but {} in another, keeping {}",
+                                name,
+                                key,
+                                kept,
+                                dropped,
+                                kept
+                            );
+                        }
+                    }
+                }
+                ("required", Value::Array(names)) => {
+                    for field in names {
+                        if !required.contains(&field) {
+                            required.push(field);
+                        }
+                    }
+                }
+                ("allOf" | "$ref" | "$id" | "$anchor", _) => {}
+                // A part's own `additionalProperties` only covers that part's properties
+                ("additionalProperties" | "unevaluatedProperties", _) if i > 0 => {}
+                (_, value) => {
+                    merged.entry(keyword).or_insert(value);
+                }
+            }
+        }
+    }
+
+    merged.insert("type".to_string(), Value::from("object"));
+    merged.insert("properties".to_string(), Value::Object(properties));
+    merged.insert("required".to_string(), Value::Array(required));
+
+    extract_struct_recursive(
+        name,
+        &Value::Object(merged),
+        ctx,
+        "#".to_string(),
+        document,
+        resolver,
+        output_path,
+        with_docs,
+    );
+    Some(name.to_string())
+}
+
+/// Flattens `schema` into the concrete schemas it is the intersection of: `schema`'s own
+/// keywords (so its docs win) followed by each `allOf` part, dereferenced and flattened.
+fn collect_all_of_parts(
+    schema: &Value,
+    document: &Value,
+    base: &SchemaBase,
+    resolver: &mut RefResolver,
+    parts: &mut Vec<(Value, SchemaBase)>,
+) -> Option<()> {
+    let mut own = schema.clone();
+    if let Some(own) = own.as_object_mut() {
+        own.remove("allOf");
+    }
+    parts.push((own, base.clone()));
+
+    for part in schema
+        .get("allOf")
+        .and_then(|a| a.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let (part, part_document, part_base) = deref_schema(part, document, base, resolver)?;
+        collect_all_of_parts(&part, &part_document, &part_base, resolver, parts)?;
+    }
+    Some(())
+}
+
+/// Folds `incoming` into `existing` (missing keywords are added, present ones kept).
+/// Returns the two types, e.g. `("string", "integer")`, when they conflict instead.
+fn merge_property_schema(existing: &mut Value, incoming: &Value) -> Option<(String, String)> {
+    let kind = |schema: &Value| schema.get("type").or_else(|| schema.get("$ref")).cloned();
+    if let (Some(kept), Some(dropped)) = (kind(existing), kind(incoming))
+        && kept != dropped
+    {
+        return Some((kept.to_string(), dropped.to_string()));
+    }
+
+    if let (Some(existing), Some(incoming)) = (existing.as_object_mut(), incoming.as_object()) {
+        for (keyword, value) in incoming {
+            existing
+                .entry(keyword.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+    None
+}
+
+/// Rewrites the relative `$ref`s in `schema` so they still resolve from another document:
+/// `#/$defs/A` → `shared/x.json#/$defs/A`, `y.json` → `shared/y.json`.
+fn rebase_refs(schema: &Value, from: &SchemaBase) -> Value {
+    match schema {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let value = match (key.as_str(), value) {
+                    ("$ref", Value::String(ref_val)) if !is_absolute_uri(ref_val) => {
+                        let (file_path, fragment) =
+                            ref_val.split_once('#').unwrap_or((ref_val, ""));
+                        let file_path = if file_path.is_empty() {
+                            from.file.clone()
+                        } else {
+                            join_uri(&from.file, file_path)
+                        };
+                        if fragment.is_empty() {
+                            Value::String(file_path)
+                        } else {
+                            Value::String(format!("{}#{}", file_path, fragment))
+                        }
+                    }
+                    _ => rebase_refs(value, from),
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        Value::Array(items) => items.iter().map(|item| rebase_refs(item, from)).collect(),
+        other => other.clone(),
+    }
+}
+
 pub fn to_snake_case(name: &str) -> String {
     let mut snake = String::new();
     for (i, ch) in name.chars().enumerate() {
@@ -867,7 +4467,7 @@ pub fn to_snake_case(name: &str) -> String {
 }
 
 pub fn to_pascal_case(name: &str) -> String {
-    name.split(|c: char| c == '_' || c == '.' || c == '-')
+    name.split(['_', '.', '-'])
         .map(|s| {
             let mut c = s.chars();
             match c.next() {
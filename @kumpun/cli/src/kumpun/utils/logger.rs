@@ -28,3 +28,11 @@ macro_rules! log_error {
         log::error!($($arg)*);
     }};
 }
+
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {{
+        $crate::kumpun::utils::logger::init_logger();
+        log::warn!($($arg)*);
+    }};
+}
@@ -13,12 +13,6 @@ fn main() {
         Commands::Dev => commands::dev::run(),
         Commands::Check => commands::check::run(),
         Commands::Docs => commands::docs::run(),
-        Commands::Generate {
-            schema,
-            target,
-            schema_dir,
-            out_dir,
-            with_docs,
-        } => commands::generate::run(schema, target, schema_dir, out_dir, with_docs),
+        Commands::Generate(args) => commands::generate::run(args),
     }
 }
@@ -0,0 +1,49 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_all_of_parts_merge_into_one_struct() {
+    let schemas: Vec<&str> = vec!["all_of.schema"];
+    let log = setup::run_generate(
+        "cli_all_of_merge_test",
+        schemas,
+        &["--merge-all-of", "--no-format-types"],
+    );
+
+    let content = common::read("tests/generated/all_of_schema.rs");
+    assert!(content.contains(
+        r#"pub struct Settings {
+    pub accent: Option<Color>,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub notifications: bool,
+    pub theme: String,
+    pub volume: Option<i32>,
+}"#
+    ));
+    assert!(content.contains("pub enum Color {"));
+    assert!(!content.contains("Part1"));
+    assert!(!content.contains("#[serde(flatten)]"));
+
+    assert!(content.contains("pub struct Conflict {\n    pub level: Option<String>,\n}"));
+    assert!(log.contains(
+        "allOf `Conflict`: property `level` is \"string\" in one part but \"integer\" in another"
+    ));
+
+    roundtrip::assert_roundtrip(
+        "cli_all_of_merge_test",
+        "all_of_schema::AllOfSchema",
+        &serde_json::json!({
+            "settings": {
+                "theme": "dark",
+                "accent": "blue",
+                "notifications": true,
+                "id": null,
+                "name": "HQ",
+                "volume": 7
+            },
+            "conflict": { "level": "high" }
+        }),
+    );
+}
@@ -0,0 +1,55 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_refs_resolve_against_base_uri() {
+    let schemas: Vec<&str> = vec!["catalog.schema"];
+    setup::run_generate(
+        "cli_base_uri_test",
+        schemas,
+        &[
+            "--map-uri",
+            "https://schemas.kumpun.dev/=shared",
+            "--no-format-types",
+        ],
+    );
+
+    let content = common::read("tests/generated/catalog_schema.rs");
+    assert!(content.contains("use crate::generated::shared::gadget::bundle::Bundle;"));
+    assert!(content.contains("use crate::generated::shared::location::Location;"));
+    assert!(content.contains("    pub bundle: Bundle,"));
+    assert!(content.contains("    pub location: Option<Location>,"));
+    assert!(content.contains("    pub unit: Option<Unit>,"));
+
+    // Relative refs inside `shared/gadget/bundle.json` resolve against its own directory
+    let bundle = common::read("tests/generated/shared/gadget/bundle.rs");
+    assert!(bundle.contains("use crate::generated::shared::address::Address;"));
+    assert!(bundle.contains("use crate::generated::shared::gadget::item::Item;"));
+    assert!(bundle.contains("    pub item: Item,"));
+    assert!(bundle.contains("    pub ship_to: Option<Address>,"));
+
+    // `#/$defs/unit` resolves inside the embedded `$id` resource, and the absolute
+    // ref to the same definition reuses the type
+    assert!(bundle.contains("pub struct Dimensions {"));
+    assert!(bundle.contains("pub enum Unit {"));
+    assert_eq!(bundle.matches("pub enum Unit {").count(), 1);
+    assert!(!content.contains("pub enum Unit {"));
+
+    let item = common::read("tests/generated/shared/gadget/item.rs");
+    assert!(item.contains("pub struct Item {"));
+
+    roundtrip::assert_roundtrip(
+        "cli_base_uri_test",
+        "catalog_schema::CatalogSchema",
+        &serde_json::json!({
+            "bundle": {
+                "item": { "name": "Smartwatch", "price": 1999 },
+                "shipTo": { "city": "Bangkok", "zip": "10110" },
+                "dimensions": { "width": 4.5, "unit": "cm" }
+            },
+            "location": { "id": "6f1c2d3e-0000-4000-8000-000000000000", "name": "HQ" },
+            "unit": "in"
+        }),
+    );
+}
@@ -0,0 +1,63 @@
+mod common;
+mod roundtrip;
+
+use assert_cmd::Command;
+use std::fs;
+
+fn generate_batch(schema_args: &[&str]) -> String {
+    let _ = fs::remove_dir_all("tests/generated");
+    let output = Command::cargo_bin("kumpun-cli")
+        .unwrap()
+        .env("RUST_LOG", "debug,error")
+        .arg("generate")
+        .args(schema_args)
+        .args([
+            "--target",
+            "rust",
+            "--schema-dir",
+            "tests/fixtures/batch",
+            "--out-dir",
+            "tests/generated",
+        ])
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_generate_schema_directory_in_one_run() {
+    let log = generate_batch(&["--all"]);
+
+    // Every top-level schema gets its file; the type they all `$ref` is generated once
+    for file in ["customer_schema", "invoice_schema", "order_schema"] {
+        let content = common::read(&format!("tests/generated/{}.rs", file));
+        assert!(content.contains("use crate::generated::shared::address::Address;"));
+        assert!(!content.contains("pub struct Address {"));
+    }
+    assert_eq!(log.matches("📦 Generated: Address →").count(), 1);
+    assert_eq!(log.matches("shared/address.rs").count(), 1);
+    let address = common::read("tests/generated/shared/address.rs");
+    assert_eq!(address.matches("pub struct Address {").count(), 1);
+    let mod_rs = common::read("tests/generated/mod.rs");
+    assert_eq!(
+        mod_rs,
+        "pub mod customer_schema;\npub mod invoice_schema;\npub mod order_schema;\npub mod shared;\n"
+    );
+
+    roundtrip::assert_roundtrip(
+        "cli_batch_test",
+        "order_schema::OrderSchema",
+        &serde_json::json!({
+            "billing": null,
+            "id": 9007199254740993_i64,
+            "shipping": { "city": "Chiang Mai", "zip": "50000" }
+        }),
+    );
+
+    // A glob picks the matching schemas only
+    generate_batch(&["--schema", "*voice*", "--schema", "order.schema"]);
+    assert!(fs::exists("tests/generated/invoice_schema.rs").unwrap());
+    assert!(fs::exists("tests/generated/order_schema.rs").unwrap());
+    assert!(!fs::exists("tests/generated/customer_schema.rs").unwrap());
+}
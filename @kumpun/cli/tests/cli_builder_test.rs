@@ -0,0 +1,96 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_default_impls_and_builders() {
+    let schemas: Vec<&str> = vec!["builder.schema"];
+    let log = setup::run_generate(
+        "cli_builder_test",
+        schemas,
+        &["--with-default", "--with-builder"],
+    );
+
+    let content = common::read("tests/generated/builder_schema.rs");
+
+    // no schema defaults → derived, otherwise the helpers fill the impl
+    assert!(
+        content.contains("#[derive(Debug, Serialize, Deserialize, Default)]\npub struct Profile {")
+    );
+    assert!(content.contains(
+        r#"impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            font_size: default_settings_font_size(),
+            theme: default_settings_theme(),
+        }
+    }
+}"#
+    ));
+    // `Plan` is an enum without a `Default`
+    assert!(!content.contains("impl Default for BuilderSchema"));
+    assert!(log.contains("`BuilderSchema` gets no impl Default: field `plan`"));
+
+    assert!(content.contains(
+        "impl BuilderSchema {\n    pub fn builder() -> BuilderSchemaBuilder {\n        Default::default()\n    }\n}"
+    ));
+    assert!(content.contains(
+        r#"    pub fn email(mut self, value: impl Into<String>) -> Self {
+        self.email = Some(value.into());
+        self
+    }"#
+    ));
+    // `build` is taken by the builder itself
+    assert!(content.contains(
+        "    pub fn with_build(mut self, value: impl Into<String>) -> Self {\n        self.build = Some(value.into());"
+    ));
+    assert!(content.contains(
+        r#"    pub fn build(self) -> Result<BuilderSchema, String> {
+        let missing = [
+            ("`build`", self.build.is_none()),
+            ("`email`", self.email.is_none()),
+            ("`plan`", self.plan.is_none()),
+        ]
+        .into_iter()
+        .filter_map(|(key, unset)| unset.then_some(key))
+        .collect::<Vec<_>>();
+        let (Some(build), Some(email), Some(plan)) = (self.build, self.email, self.plan) else {
+            return Err(format!("missing required fields: {}", missing.join(", ")));
+        };
+        Ok(BuilderSchema {
+            build,
+            email,
+            locale: self.locale.or_else(default_builder_schema_locale),
+            plan,
+            profile: self.profile,
+            retries: self.retries.or_else(default_builder_schema_retries),
+            settings: self.settings,
+            tags: self.tags,
+        })
+    }"#
+    ));
+
+    // every missing required field is reported at once
+    roundtrip::assert_main(
+        "cli_builder_test",
+        r#"    let error = generated::builder_schema::BuilderSchema::builder()
+        .email("a@example.com")
+        .build()
+        .unwrap_err();
+    assert_eq!(error, "missing required fields: `build`, `plan`");"#,
+    );
+    roundtrip::assert_roundtrip(
+        "cli_builder_test",
+        "builder_schema::BuilderSchema",
+        &serde_json::json!({
+            "email": "a@example.com",
+            "plan": "pro",
+            "build": "2024.1",
+            "retries": 1,
+            "locale": "th",
+            "tags": null,
+            "profile": { "name": "Nanta", "bio": null },
+            "settings": { "theme": "light", "fontSize": 12 }
+        }),
+    );
+}
@@ -0,0 +1,57 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_camel_case_keys_round_trip() {
+    let schemas: Vec<&str> = vec!["camel_case.schema"];
+    setup::run_generate("cli_camel_case_test", schemas, &[]);
+
+    let content = common::read("tests/generated/camel_case_schema.rs");
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CamelCaseSchema {"#
+    ));
+    assert!(content.contains("    pub user_id: String,"));
+    assert!(content.contains("    pub is_verified: Option<bool>,"));
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HomeAddress {
+    pub postal_code: String,
+    pub street_line1: String,
+}"#
+    ));
+
+    // Mixed conventions fall back to a rename per field
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditTrail {
+    #[serde(rename = "X-Request-Id")]
+    pub x_request_id: Option<String>,
+    pub created_by: Option<String>,
+    #[serde(rename = "modifiedAt")]
+    pub modified_at: Option<String>,
+}"#
+    ));
+
+    roundtrip::assert_roundtrip(
+        "cli_camel_case_test",
+        "camel_case_schema::CamelCaseSchema",
+        &serde_json::json!({
+            "userId": "u-001",
+            "displayName": "Kumpun",
+            "isVerified": true,
+            "homeAddress": { "streetLine1": "123 Main Street", "postalCode": "10110" },
+            "auditTrail": {
+                "created_by": "admin",
+                "modifiedAt": "2024-01-01T00:00:00Z",
+                "X-Request-Id": "req-42"
+            }
+        }),
+    );
+}
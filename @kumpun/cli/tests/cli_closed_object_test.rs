@@ -0,0 +1,37 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_closed_objects_deny_unknown_fields() {
+    let schemas: Vec<&str> = vec!["closed.schema"];
+    setup::run_generate("cli_closed_object_test", schemas, &[]);
+
+    let content = common::read("tests/generated/closed_schema.rs");
+    assert!(content.contains("#[serde(deny_unknown_fields)]\npub struct ClosedSchema {"));
+    assert!(content.contains("#[serde(deny_unknown_fields)]\npub struct Strict {"));
+    assert!(content.contains("#[serde(deny_unknown_fields)]\npub struct EventClick {"));
+
+    // `properties` plus an `additionalProperties` schema keeps the rest in `extra`
+    assert!(content.contains(
+        r#"#[derive(Debug, Serialize, Deserialize)]
+pub struct Labels {
+    pub env: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, String>,
+}"#
+    ));
+    assert!(content.contains("    pub counts: Option<HashMap<String, i32>>,"));
+
+    roundtrip::assert_roundtrip(
+        "cli_closed_object_test",
+        "closed_schema::ClosedSchema",
+        &serde_json::json!({
+            "name": "kumpun",
+            "labels": { "env": "prod", "team": "core" },
+            "strict": { "on": true },
+            "counts": { "a": 1 },
+            "event": { "kind": "click", "x": 3 }
+        }),
+    );
+}
@@ -14,7 +14,7 @@ fn test_struct_field_doc_comments_are_generated() {
         r#"
     /// Unique identifier (UUID) for the entity.
     /// Example: "550e8400-e29b-41d4-a716-446655440000"
-    pub id: String,
+    pub id: Uuid,
     "#
     ));
     assert!(content.contains(
@@ -42,6 +42,7 @@ fn test_struct_field_doc_comments_are_generated() {
         r#"
 /// Basic profile information.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Profile {
     /// Detailed nested address for profile.
     /// Example: {"line1":"123 Main Street","line2":"Apt 4B","zip":"10110"}
@@ -68,8 +69,11 @@ pub struct Profile {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Status {
+    #[serde(rename = "active")]
     Active,
+    #[serde(rename = "inactive")]
     Inactive,
+    #[serde(rename = "banned")]
     Banned,
 }"#
     ));
@@ -95,7 +99,7 @@ pub enum Status {
         r#"
 
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(tag = "type")]
+#[serde(untagged)]
 pub enum Preferences {
     /// User prefers email communication only.
     /// Example: {"email":"user@example.com"}
@@ -0,0 +1,59 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_conditionals_become_enums_or_runtime_checks() {
+    let schemas: Vec<&str> = vec!["conditions.schema"];
+    setup::run_generate("cli_conditional_test", schemas, &[]);
+
+    let content = common::read("tests/generated/conditions_schema.rs");
+
+    // `if` pins `method` → one struct per shape, values sharing a branch share it
+    assert!(content.contains(
+        r#"#[serde(tag = "method")]
+pub enum Payment {
+    #[serde(rename = "card")]
+    Card(PaymentCard),
+    #[serde(rename = "bank")]
+    Bank(PaymentBank),
+    #[serde(rename = "cash")]
+    Cash(PaymentBank),
+}"#
+    ));
+    assert!(content.contains(
+        "pub struct PaymentCard {\n    pub amount: f64,\n    pub card_number: String,\n}"
+    ));
+    assert!(content.contains(
+        "pub struct PaymentBank {\n    pub amount: f64,\n    pub iban: Option<String>,\n}"
+    ));
+
+    // anything else is checked by `validate_conditions()`
+    assert!(content.contains("    pub gift_message: Option<String>,"));
+    assert!(content.contains("    pub fn validate_conditions(&self) -> Result<(), String> {"));
+    assert!(content.contains(
+        r#"        let condition = value["country"].is_null() || value["country"] == serde_json::json!("US");
+        if condition && value["state"].is_null() {
+            return Err("`state` is required when the `if` condition holds".to_string());
+        }"#
+    ));
+    assert!(content.contains(
+        r#"        if !value["postalCode"].is_null() && value["state"].is_null() {
+            return Err("`state` is required when `postalCode` is present".to_string());
+        }"#
+    ));
+    assert!(content.contains(
+        r#"        if !value["giftWrap"].is_null() && value["giftMessage"].is_null() {"#
+    ));
+
+    roundtrip::assert_roundtrip(
+        "cli_conditional_test",
+        "conditions_schema::Payment",
+        &serde_json::json!({ "method": "card", "amount": 9.5, "cardNumber": "4242" }),
+    );
+    roundtrip::assert_rejects(
+        "cli_conditional_test",
+        "conditions_schema::Payment",
+        &serde_json::json!({ "method": "card", "amount": 9.5 }),
+    );
+}
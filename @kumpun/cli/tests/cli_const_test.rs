@@ -0,0 +1,47 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_const_only_accepts_its_value() {
+    let schemas: Vec<&str> = vec!["consts.schema"];
+    setup::run_generate("cli_const_test", schemas, &["--enum-fallback"]);
+
+    let content = common::read("tests/generated/consts_schema.rs");
+    assert!(content.contains("    pub api_version: ApiVersion,"));
+    assert!(content.contains("    pub schema_version: SchemaVersion,"));
+    assert!(content.contains("    pub live: Option<Live>,"));
+    assert!(content.contains("    pub origin: Option<Origin>,"));
+
+    // a single variant, even with `--enum-fallback`
+    assert!(content.contains(
+        r#"pub enum ApiVersion {
+    #[serde(rename = "v2")]
+    V2,
+}"#
+    ));
+    assert!(content.contains(
+        "#[serde(try_from = \"i64\", into = \"i64\")]\npub enum SchemaVersion {\n    V3,\n}"
+    ));
+    assert!(content.contains("pub struct Origin;"));
+    assert!(content.contains("        if value == serde_json::json!([0,0]) {"));
+
+    let valid = serde_json::json!({
+        "apiVersion": "v2",
+        "schemaVersion": 3,
+        "live": true,
+        "origin": [0, 0]
+    });
+    roundtrip::assert_roundtrip("cli_const_test", "consts_schema::ConstsSchema", &valid);
+
+    for (field, wrong) in [
+        ("apiVersion", serde_json::json!("v1")),
+        ("schemaVersion", serde_json::json!(2)),
+        ("live", serde_json::json!(false)),
+        ("origin", serde_json::json!([1, 0])),
+    ] {
+        let mut payload = valid.clone();
+        payload[field] = wrong;
+        roundtrip::assert_rejects("cli_const_test", "consts_schema::ConstsSchema", &payload);
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use assert_cmd::Command;
+use std::{fs, path::Path};
+
+fn generate_crate(out_dir: &Path, extra_args: &[&str]) {
+    let output = Command::cargo_bin("kumpun-cli")
+        .unwrap()
+        .args(["generate", "--all", "--target", "rust"])
+        .args(["--schema-dir", "tests/fixtures/batch", "--out-dir"])
+        .arg(out_dir)
+        .args(["--crate", "api-types"])
+        .args(extra_args)
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_generate_standalone_crate() {
+    // Outside this workspace, so the crate builds on its own
+    let out_dir = std::env::temp_dir().join("kumpun-cli-crate-test/api-types");
+    let _ = fs::remove_dir_all(&out_dir);
+    generate_crate(&out_dir, &[]);
+
+    // The version comes from the schema registry; only used dependencies are listed
+    let manifest = common::read(out_dir.join("Cargo.toml").to_str().unwrap());
+    assert!(manifest.contains("name = \"api-types\"\nversion = \"2.3.0\"\n"));
+    assert!(manifest.contains("serde = { version = \"1\", features = [\"derive\"] }"));
+    assert!(manifest.contains("serde_json = \"1\""));
+    assert!(!manifest.contains("chrono") && !manifest.contains("validator"));
+
+    let lib = common::read(out_dir.join("src/lib.rs").to_str().unwrap());
+    assert_eq!(
+        lib,
+        "pub mod customer_schema;\npub mod invoice_schema;\npub mod order_schema;\npub mod shared;\n"
+    );
+    assert!(!out_dir.join("src/mod.rs").exists());
+    let order = common::read(out_dir.join("src/order_schema.rs").to_str().unwrap());
+    assert!(order.contains("use crate::shared::address::Address;"));
+
+    build_crate(&out_dir);
+
+    // Regenerating over the existing crate keeps it buildable; `--crate-version` wins
+    // over the registry
+    generate_crate(&out_dir, &["--crate-version", "3.0.0-rc.1"]);
+    let manifest = common::read(out_dir.join("Cargo.toml").to_str().unwrap());
+    assert!(manifest.contains("version = \"3.0.0-rc.1\""));
+    let lib = common::read(out_dir.join("src/lib.rs").to_str().unwrap());
+    assert!(!lib.contains("pub mod lib;"));
+    build_crate(&out_dir);
+
+    // Crates named by `x-rust-type` are listed when known, and warned about otherwise
+    let out_dir = std::env::temp_dir().join("kumpun-cli-crate-test/money-types");
+    let _ = fs::remove_dir_all(&out_dir);
+    let output = Command::cargo_bin("kumpun-cli")
+        .unwrap()
+        .args(["generate", "--schema", "money.schema", "--target", "rust"])
+        .args(["--schema-dir", "tests/fixtures/schemas", "--out-dir"])
+        .arg(&out_dir)
+        .args(["--crate", "money-types", "--crate-version", "1.0.0"])
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+    let manifest = common::read(out_dir.join("Cargo.toml").to_str().unwrap());
+    assert!(manifest.contains("uuid = { version = \"1\", features = [\"serde\"] }"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains(
+        "The generated code uses `rust_decimal`, which isn't in Cargo.toml; add it as a dependency"
+    ));
+}
+
+fn build_crate(out_dir: &Path) {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let build = std::process::Command::new(cargo)
+        .args(["build", "--quiet", "--offline", "--manifest-path"])
+        .arg(out_dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", ".tmp/roundtrip/target")
+        .output()
+        .expect("Failed to run cargo");
+    assert!(
+        build.status.success(),
+        "Generated crate failed to build:\n{}",
+        String::from_utf8_lossy(&build.stderr)
+    );
+}
@@ -21,46 +21,54 @@ pub struct DefaultConstTestSchema"#
     ));
     assert!(schema.contains(
         r#"
-    #[serde(default = "default_age")]
+    #[serde(default = "default_default_const_test_schema_age")]
     pub age: Option<i32>"#
     ));
     assert!(schema.contains(
         r#"
-    #[serde(default = "default_is_admin")]
+    #[serde(default = "default_default_const_test_schema_is_admin")]
     pub is_admin: Option<bool>"#
     ));
     assert!(schema.contains(
         r#"
-    #[serde(default = "default_role")]
+    #[serde(default = "default_default_const_test_schema_role")]
     pub role: Option<String>"#
     ));
     assert!(schema.contains(
         r#"
-    #[serde(default = "default_status")]
-    pub status: String"#
+    #[serde(default = "default_default_const_test_schema_status")]
+    pub status: Status"#
     ));
+    // `const` only accepts its own value
     assert!(schema.contains(
         r#"
-fn default_age() -> i32 {
-    18
+pub enum Status {
+    #[serde(rename = "active")]
+    Active,
 }"#
     ));
     assert!(schema.contains(
         r#"
-fn default_is_admin() -> bool {
-    false
+fn default_default_const_test_schema_age() -> Option<i32> {
+    Some(18)
 }"#
     ));
     assert!(schema.contains(
         r#"
-fn default_role() -> String {
-    "guest".to_string()
+fn default_default_const_test_schema_is_admin() -> Option<bool> {
+    Some(false)
 }"#
     ));
     assert!(schema.contains(
         r#"
-fn default_status() -> String {
-    "active".to_string()
+fn default_default_const_test_schema_role() -> Option<String> {
+    Some("guest".to_string())
+}"#
+    ));
+    assert!(schema.contains(
+        r#"
+fn default_default_const_test_schema_status() -> Status {
+    Status::Active
 }"#
     ));
 }
@@ -0,0 +1,31 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_derive_default_alongside_with_default() {
+    let schemas: Vec<&str> = vec!["default_derive.schema"];
+    setup::run_generate(
+        "cli_default_derive_test",
+        schemas,
+        &["--with-default", "--derive", "Default"],
+    );
+
+    let content = common::read("tests/generated/default_derive_schema.rs");
+    // the hand-written impl stands in for the derive
+    assert!(
+        content
+            .contains("#[derive(Debug, Serialize, Deserialize)]\npub struct DefaultDeriveSchema {")
+    );
+    assert!(content.contains("impl Default for DefaultDeriveSchema {"));
+    assert!(
+        content.contains("#[derive(Debug, Serialize, Deserialize, Default)]\npub struct Layout {")
+    );
+    assert!(!content.contains("impl Default for Layout"));
+
+    roundtrip::assert_roundtrip(
+        "cli_default_derive_test",
+        "default_derive_schema::DefaultDeriveSchema",
+        &serde_json::json!({ "theme": "light", "layout": { "columns": 2 } }),
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_defaults_render_as_rust_expressions() {
+    let schemas: Vec<&str> = vec!["defaults.schema"];
+    setup::run_generate("cli_default_value_test", schemas, &[]);
+
+    let content = common::read("tests/generated/defaults_schema.rs");
+    assert!(content.contains(
+        "fn default_defaults_schema_status() -> Option<Status> {\n    Some(Status::Draft)\n}"
+    ));
+    assert!(content.contains(
+        r#"fn default_defaults_schema_greeting() -> Option<String> {
+    Some("say \"hi\" \\ wave".to_string())
+}"#
+    ));
+    assert!(content.contains(
+        r#"fn default_defaults_schema_tags() -> Option<Vec<String>> {
+    Some(vec!["a".to_string(), "b".to_string()])
+}"#
+    ));
+    assert!(content.contains(
+        r#"fn default_defaults_schema_limits() -> Option<HashMap<String, i32>> {
+    Some(HashMap::from([("daily".to_string(), 10)]))
+}"#
+    ));
+    assert!(content.contains("fn default_defaults_schema_note() -> Option<String> {\n    None\n}"));
+    assert!(content.contains(
+        r#"fn default_defaults_schema_paging() -> Option<Paging> {
+    Some(Paging { cursor: None, size: 20, status: default_paging_status() })
+}"#
+    ));
+    assert!(
+        content.contains("fn default_defaults_schema_ratio() -> Option<f64> {\n    Some(1.0)\n}")
+    );
+    assert!(content.contains(
+        r#"fn default_defaults_schema_anything() -> Option<serde_json::Value> {
+    Some(serde_json::json!({"k":[1,true]}))
+}"#
+    ));
+
+    // helpers are scoped per struct, so two `status` fields don't collide
+    assert!(content.contains("    #[serde(default = \"default_paging_status\")]"));
+    assert!(content.contains("fn default_paging_status() -> Option<String> {"));
+
+    // helper names come from the Rust field name, not the JSON key
+    assert!(content.contains(
+        "    #[serde(rename = \"$schema\")]\n    #[serde(default = \"default_defaults_schema_schema\")]"
+    ));
+    assert!(content.contains("fn default_defaults_schema_type() -> Option<String> {"));
+
+    roundtrip::assert_roundtrip(
+        "cli_default_value_test",
+        "defaults_schema::DefaultsSchema",
+        &serde_json::json!({
+            "status": "published",
+            "greeting": "hello",
+            "tags": [],
+            "limits": {},
+            "note": "n",
+            "paging": { "size": 5, "cursor": "c", "status": "closed" },
+            "ratio": 0.5,
+            "anything": 1,
+            "$schema": "v2",
+            "type": "post"
+        }),
+    );
+}
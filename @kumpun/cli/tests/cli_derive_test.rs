@@ -0,0 +1,58 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+use assert_cmd::Command;
+
+#[test]
+fn test_configurable_derives_and_attributes() {
+    let schemas: Vec<&str> = vec!["derives.schema"];
+    setup::run_generate("cli_derive_test", schemas, &["--derive", "Clone,PartialEq"]);
+
+    let content = common::read("tests/generated/derives_schema.rs");
+    assert!(
+        content.contains(
+            "#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]\npub enum Status {"
+        )
+    );
+    // `x-rust-derive` adds to `--derive`
+    assert!(content.contains(
+        "#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]\npub struct Money {"
+    ));
+    // `x-rust-attributes` go right below the derives
+    assert!(content.contains(
+        "#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]\n#[non_exhaustive]\npub struct DerivesSchema {"
+    ));
+
+    roundtrip::assert_roundtrip(
+        "cli_derive_test",
+        "derives_schema::DerivesSchema",
+        &serde_json::json!({
+            "total": { "currency": "THB", "cents": 1999 },
+            "status": "paid",
+            "weight": 1.5
+        }),
+    );
+
+    // `Eq` can't be derived over an `f64` field
+    let output = Command::cargo_bin("kumpun-cli")
+        .unwrap()
+        .args([
+            "generate",
+            "--schema",
+            "derives_invalid.schema",
+            "--target",
+            "rust",
+            "--schema-dir",
+            "tests/fixtures/schemas",
+            "--out-dir",
+            "tests/generated",
+        ])
+        .output()
+        .expect("Failed to run command");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .contains("`Price` can't derive `Eq`: field `amount` holds `f64`")
+    );
+}
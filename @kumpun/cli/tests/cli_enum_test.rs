@@ -0,0 +1,83 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_wire_safe_enums() {
+    let schemas: Vec<&str> = vec!["enums.schema"];
+    setup::run_generate("cli_enum_test", schemas, &[]);
+
+    let content = common::read("tests/generated/enums_schema.rs");
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize)]
+pub enum State {
+    #[serde(rename = "in-progress")]
+    InProgress,
+    #[serde(rename = "v1.2")]
+    V12,
+    #[serde(rename = "123")]
+    V123,
+    #[serde(rename = "ACTIVE")]
+    Active,
+    #[serde(rename = "active")]
+    Active2,
+    Done,
+}"#
+    ));
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "i64", into = "i64")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}"#
+    ));
+    assert!(content.contains("            -1 => Ok(Self::VNeg1),"));
+    assert!(content.contains(
+        r#"#[serde(try_from = "serde_json::Value", into = "serde_json::Value")]
+pub enum Timeout {
+    Auto,
+    V0,
+    V2_5,
+    False,
+}"#
+    ));
+    assert!(content.contains("    pub color: Option<Color>,"));
+    assert!(!content.contains("Unknown"));
+
+    roundtrip::assert_roundtrip(
+        "cli_enum_test",
+        "enums_schema::EnumsSchema",
+        &serde_json::json!({
+            "state": "v1.2",
+            "priority": 3,
+            "retries": -1,
+            "timeout": 2.5,
+            "color": null
+        }),
+    );
+
+    // With the fallback, values added by the server later still deserialize
+    let schemas: Vec<&str> = vec!["enums.schema"];
+    setup::run_generate("cli_enum_fallback_test", schemas, &["--enum-fallback"]);
+
+    let content = common::read("tests/generated/enums_schema.rs");
+    assert!(content.contains("    #[serde(other)]\n    Unknown,\n}"));
+    assert!(content.contains("    Unknown(i64),\n}"));
+    assert!(content.contains("    Unknown(serde_json::Value),\n}"));
+
+    roundtrip::assert_roundtrip(
+        "cli_enum_fallback_test",
+        "enums_schema::EnumsSchema",
+        &serde_json::json!({
+            "state": "Done",
+            "priority": 7,
+            "retries": 0,
+            "timeout": "never",
+            "color": "green"
+        }),
+    );
+}
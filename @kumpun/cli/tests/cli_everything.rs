@@ -9,9 +9,10 @@ fn test_generate_struct_from_everything_schema() {
 
     assert!(std::path::Path::new("tests/generated/everything_example.rs").exists());
     let content = common::read("tests/generated/everything_example.rs");
-    assert!(content.contains("pub struct EverythingExample"));
-    assert!(content.contains("pub id: String"));
-    assert!(content.contains("pub age: Option<i32>"));
+    assert!(content.contains("#[serde(rename_all = \"camelCase\")]\npub struct EverythingExample"));
+    assert!(content.contains("pub id: Uuid"));
+    assert!(content.contains("use uuid::Uuid;"));
+    assert!(content.contains("pub age: Option<u64>"));
     assert!(content.contains("pub status: Status"));
     assert!(content.contains("pub profile: Profile"));
     assert!(content.contains("pub tags: Vec<String>"));
@@ -0,0 +1,67 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_format_types() {
+    let schemas: Vec<&str> = vec!["formats.schema"];
+    setup::run_generate("cli_format_types_test", schemas, &[]);
+
+    let content = common::read("tests/generated/formats_schema.rs");
+    assert!(content.contains("use chrono::DateTime;"));
+    assert!(content.contains("use chrono::NaiveDate;"));
+    assert!(content.contains("use chrono::Utc;"));
+    assert!(content.contains("use std::net::IpAddr;"));
+    assert!(content.contains("use url::Url;"));
+    assert!(content.contains("use uuid::Uuid;"));
+    assert!(content.contains("    pub id: Uuid,"));
+    assert!(content.contains("    pub created_at: DateTime<Utc>,"));
+    assert!(content.contains("    pub birthday: Option<NaiveDate>,"));
+    assert!(content.contains("    pub homepage: Option<Url>,"));
+    assert!(content.contains("    pub email: Option<String>,"));
+    assert!(content.contains("    pub last_login_ip: Option<IpAddr>,"));
+    assert!(content.contains("    pub history: Option<Vec<DateTime<Utc>>>,"));
+    // a generated type can't take the name of an import
+    assert!(content.contains("pub struct FormatsSchemaUrl {\n    pub href: Option<Url>,"));
+    assert!(content.contains("    pub link: Option<FormatsSchemaUrl>,"));
+
+    let location = common::read("tests/generated/shared/location.rs");
+    assert!(location.contains("use serde::{Deserialize, Serialize};\nuse uuid::Uuid;"));
+    assert!(location.contains("    pub id: Option<Uuid>,"));
+
+    roundtrip::assert_roundtrip(
+        "cli_format_types_test",
+        "formats_schema::FormatsSchema",
+        &serde_json::json!({
+            "id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "createdAt": "2024-05-01T08:30:00Z",
+            "birthday": "1990-12-31",
+            "homepage": "https://kumpun.io/",
+            "email": "dev@kumpun.io",
+            "lastLoginIp": "10.0.0.1",
+            "history": ["2024-04-30T23:59:59Z"],
+            "location": { "id": "67e55044-10b1-426f-9247-bb680e5fe0c9", "name": "Bangkok" },
+            "link": { "href": "https://kumpun.io/docs", "label": "Docs" }
+        }),
+    );
+
+    // Mapping switched off, with a single override on top
+    let schemas: Vec<&str> = vec!["formats.schema"];
+    setup::run_generate(
+        "cli_format_types_disabled_test",
+        schemas,
+        &[
+            "--no-format-types",
+            "--format-type",
+            "email=crate::types::Email",
+        ],
+    );
+
+    let content = common::read("tests/generated/formats_schema.rs");
+    assert!(!content.contains("use uuid::Uuid;"));
+    assert!(!content.contains("use chrono::"));
+    assert!(content.contains("use crate::types::Email;"));
+    assert!(content.contains("    pub id: String,"));
+    assert!(content.contains("    pub created_at: String,"));
+    assert!(content.contains("    pub email: Option<Email>,"));
+}
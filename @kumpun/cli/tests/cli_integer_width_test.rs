@@ -0,0 +1,61 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_integer_width_follows_bounds_and_format() {
+    let schemas: Vec<&str> = vec!["integers.schema"];
+    setup::run_generate("cli_integer_width_test", schemas, &[]);
+
+    let content = common::read("tests/generated/integers_schema.rs");
+    assert!(content.contains("    pub small: Option<u8>,"));
+    assert!(content.contains("    pub port: Option<u16>,"));
+    assert!(content.contains("    pub count: Option<u64>,"));
+    assert!(content.contains("    pub offset: Option<i32>,"));
+    assert!(content.contains("    pub created_at_millis: Option<u64>,"));
+    assert!(content.contains("    pub delta: Option<i64>,"));
+    assert!(content.contains("    pub id: Option<i64>,"));
+    assert!(content.contains("    pub flags: Option<u32>,"));
+    assert!(content.contains("    pub plain: Option<i32>,"));
+    // fractional and draft-04 boolean bounds: `-0.5..256` holds only `0..=255`
+    assert!(content.contains("    pub level: Option<u8>,"));
+
+    // `default_*` helpers return the field's own type
+    assert!(content.contains(
+        "    #[serde(default = \"default_integers_schema_retries\")]\n    pub retries: Option<u8>,"
+    ));
+    assert!(
+        content.contains("fn default_integers_schema_retries() -> Option<u8> {\n    Some(3)\n}")
+    );
+    assert!(
+        content.contains("fn default_integers_schema_ratio() -> Option<f64> {\n    Some(1.0)\n}")
+    );
+    // the type widens to hold an out-of-bounds `default`…
+    assert!(
+        content.contains("fn default_integers_schema_limit() -> Option<u16> {\n    Some(1000)\n}")
+    );
+    // …but a `format` is kept, and a `default` it can't hold gets no helper
+    assert!(content.contains("    pub tag: Option<u8>,"));
+    assert!(!content.contains("fn default_integers_schema_tag()"));
+
+    roundtrip::assert_roundtrip(
+        "cli_integer_width_test",
+        "integers_schema::IntegersSchema",
+        &serde_json::json!({
+            "small": 200,
+            "port": 8080,
+            "count": 5_000_000_000u64,
+            "offset": -3,
+            "createdAtMillis": 1_718_000_000_000u64,
+            "delta": -4_999_999_999i64,
+            "id": 9_007_199_254_740_993i64,
+            "flags": 4_000_000_000u32,
+            "plain": 1,
+            "retries": 3,
+            "ratio": 0.5,
+            "level": 255,
+            "limit": 50,
+            "tag": 7
+        }),
+    );
+}
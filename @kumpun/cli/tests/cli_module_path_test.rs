@@ -0,0 +1,63 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+use assert_cmd::Command;
+use std::fs;
+
+#[test]
+fn test_module_path_and_relative_imports() {
+    // `super::` imports don't depend on where the output is mounted
+    let schemas: Vec<&str> = vec!["overrides.schema"];
+    setup::run_generate("cli_module_path_test", schemas, &["--relative-imports"]);
+
+    let content = common::read("tests/generated/overrides_schema.rs");
+    assert!(content.contains("use super::models::postal::PostalAddress;"));
+    let postal = common::read("tests/generated/models/postal.rs");
+    assert!(postal.contains("use super::super::overrides_schema::Geo;"));
+    assert!(!content.contains("crate::generated") && !postal.contains("crate::generated"));
+
+    roundtrip::assert_roundtrip(
+        "cli_module_path_test",
+        "overrides_schema::Account",
+        &serde_json::json!({
+            "address": { "city": "Khon Kaen", "geo": null },
+            "id": 1,
+            "ip": null,
+            "nextIp": null,
+            "role": "member"
+        }),
+    );
+
+    // An explicit module path
+    let schemas: Vec<&str> = vec!["overrides.schema"];
+    setup::run_generate(
+        "cli_module_path_test",
+        schemas,
+        &["--module-path", "crate::api::types"],
+    );
+    let content = common::read("tests/generated/overrides_schema.rs");
+    assert!(content.contains("use crate::api::types::models::postal::PostalAddress;"));
+
+    // Without one, it follows `--out-dir` below `src/`
+    let out_dir = ".tmp/module-path/my-api/src/api/types";
+    let _ = fs::remove_dir_all(out_dir);
+    let output = Command::cargo_bin("kumpun-cli")
+        .unwrap()
+        .args([
+            "generate",
+            "--schema",
+            "overrides.schema",
+            "--target",
+            "rust",
+            "--schema-dir",
+            "tests/fixtures/schemas",
+            "--out-dir",
+            out_dir,
+        ])
+        .output()
+        .expect("Failed to run command");
+    assert!(output.status.success());
+    let postal = common::read(&format!("{}/models/postal.rs", out_dir));
+    assert!(postal.contains("use crate::api::types::overrides_schema::Geo;"));
+}
@@ -0,0 +1,51 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_multi_type_unions() {
+    let schemas: Vec<&str> = vec!["multi_type.schema"];
+    setup::run_generate("cli_multi_type_test", schemas, &["--with-docs"]);
+
+    let content = common::read("tests/generated/multi_type_schema.rs");
+    assert!(content.contains(
+        r#"
+    /// Nickname, explicitly nullable.
+    pub nickname: Option<String>,"#
+    ));
+    assert!(content.contains("    pub score: Option<i32>,"));
+    assert!(content.contains("    pub payload: Payload,"));
+    assert!(content.contains("    pub limit: Option<Limit>,"));
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Payload {
+    String(String),
+    Integer(i32),
+    Object(PayloadObject),
+    Number(f64),
+}"#
+    ));
+    assert!(content.contains("pub struct PayloadObject {\n    pub label: String,\n}"));
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Limit {
+    Integer(i32),
+    String(String),
+}"#
+    ));
+
+    roundtrip::assert_roundtrip(
+        "cli_multi_type_test",
+        "multi_type_schema::MultiTypeSchema",
+        &serde_json::json!({
+            "nickname": null,
+            "score": 42,
+            "payload": { "label": "boxed" },
+            "limit": "unlimited"
+        }),
+    );
+}
@@ -0,0 +1,42 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_colliding_type_names_are_qualified() {
+    let schemas: Vec<&str> = vec!["naming.schema"];
+    let log = setup::run_generate("cli_name_collision_test", schemas, &[]);
+
+    let content = common::read("tests/generated/naming_schema.rs");
+
+    // Identical shapes share one type, including one that was already renamed
+    assert_eq!(content.matches("pub struct Address {").count(), 1);
+    assert_eq!(content.matches("pub enum ProfileStatus {").count(), 1);
+    assert!(content.contains(
+        "pub struct Billing {\n    pub address: Option<Address>,\n    pub status: Option<Status>,\n}"
+    ));
+
+    // Different shapes under the same name are qualified with their parent
+    assert!(content.contains(
+        "pub struct Profile {\n    pub address: Option<Address>,\n    pub status: Option<ProfileStatus>,\n}"
+    ));
+    assert!(content.contains(
+        "pub struct Shipping {\n    pub address: Option<ShippingAddress>,\n    pub status: Option<ProfileStatus>,\n}"
+    ));
+    assert!(content.contains("pub struct ShippingAddress {"));
+    assert!(!content.contains("ShippingStatus"));
+
+    assert!(log.contains("Renamed 2 colliding type name(s)"));
+    assert!(log.contains("Status → ProfileStatus (in Profile)"));
+    assert!(log.contains("Address → ShippingAddress (in Shipping)"));
+
+    roundtrip::assert_roundtrip(
+        "cli_name_collision_test",
+        "naming_schema::NamingSchema",
+        &serde_json::json!({
+            "profile": { "address": { "street": "Main", "city": "Chiang Mai" }, "status": "active" },
+            "billing": { "address": { "street": "Side", "city": "Bangkok" }, "status": "overdue" },
+            "shipping": { "address": { "line1": "1 Road", "country": "TH" }, "status": "banned" }
+        }),
+    );
+}
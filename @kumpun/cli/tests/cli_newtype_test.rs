@@ -0,0 +1,71 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_validating_newtypes() {
+    let schemas: Vec<&str> = vec!["newtypes.schema", "newtypes_contact.schema"];
+    setup::run_generate("cli_newtype_test", schemas, &["--with-newtypes"]);
+
+    let content = common::read("tests/generated/newtypes_schema.rs");
+    assert!(content.contains("#[serde(try_from = \"String\")]\npub struct Username(String);"));
+    assert!(content.contains("impl TryFrom<String> for Username {"));
+    assert!(content.contains("if !PATTERN.is_match(&value) {"));
+    // `minimum: 0` is already guaranteed by `u8`, only the upper bound is checked
+    assert!(content.contains("pub struct Percentage(u8);"));
+    assert!(content.contains("        if value > 100 {"));
+    assert!(!content.contains("value < 0"));
+    assert!(content.contains("pub struct Ratio(f64);"));
+    // fractional and draft-04 boolean bounds: `> 1.5` on an integer is `>= 2`
+    assert!(content.contains("pub struct Level(u8);"));
+    assert!(content.contains("        if value < 2 {"));
+    assert!(content.contains("        if value > 10 {"));
+    assert!(content.contains("        if value <= 0.0 {"));
+    assert!(content.contains("        if value >= 50.0 {"));
+    // bounds a `u8` can't hold would not compile, so they're left out
+    assert!(content.contains("pub struct Slot(u8);"));
+    assert!(!content.contains("1000"));
+    assert!(!content.contains("% 500"));
+    assert!(content.contains("    pub tags: Option<Vec<TagsItem>>,"));
+    assert!(content.contains("    pub nickname: Option<String>,"));
+
+    // A newtype behind a shared `$ref` is generated once and used by both schemas
+    let email = common::read("tests/generated/shared/email.rs");
+    assert_eq!(email.matches("pub struct Email(String);").count(), 1);
+    assert!(!content.contains("pub struct Email("));
+    assert!(content.contains("use crate::generated::shared::email::Email;"));
+    let contact = common::read("tests/generated/newtypes_contact_schema.rs");
+    assert!(contact.contains("use crate::generated::shared::email::Email;"));
+    assert!(contact.contains("    pub primary: Email,"));
+
+    roundtrip::assert_roundtrip(
+        "cli_newtype_test",
+        "newtypes_schema::NewtypesSchema",
+        &serde_json::json!({
+            "email": "dev@kumpun.io",
+            "username": "kumpun_dev",
+            "percentage": 42,
+            "ratio": 0.5,
+            "level": 2,
+            "slot": 4,
+            "weight": 0.5,
+            "nickname": null,
+            "tags": ["api"]
+        }),
+    );
+    roundtrip::assert_rejects(
+        "cli_newtype_test",
+        "newtypes_schema::NewtypesSchema",
+        &serde_json::json!({ "email": "dev@kumpun.io", "username": "Kumpun!", "percentage": 42 }),
+    );
+    roundtrip::assert_rejects(
+        "cli_newtype_test",
+        "newtypes_schema::NewtypesSchema",
+        &serde_json::json!({ "email": "dev@kumpun.io", "username": "kumpun", "percentage": 42, "level": 1 }),
+    );
+    roundtrip::assert_rejects(
+        "cli_newtype_test",
+        "newtypes_contact_schema::NewtypesContactSchema",
+        &serde_json::json!({ "primary": "not-an-email" }),
+    );
+}
@@ -0,0 +1,55 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_one_of_uses_discriminator() {
+    let schemas: Vec<&str> = vec!["one_of.schema"];
+    let log = setup::run_generate("cli_one_of_test", schemas, &[]);
+
+    let content = common::read("tests/generated/one_of_schema.rs");
+
+    // Inferred from a property every variant pins with `const` / a single-value `enum`
+    assert!(content.contains(
+        r#"#[serde(tag = "kind")]
+pub enum Shape {
+    #[serde(rename = "circle")]
+    Circle(ShapeCircle),
+    #[serde(rename = "square")]
+    Square(ShapeSquare),
+}"#
+    ));
+    assert!(content.contains("pub struct ShapeCircle {\n    pub radius: f64,\n}"));
+    assert!(!content.contains("pub kind:"));
+
+    // OpenAPI `discriminator`, with a `mapping` entry and the schema name as default
+    assert!(content.contains(
+        r#"#[serde(tag = "petType")]
+pub enum Pet {
+    #[serde(rename = "doggo")]
+    Dog(PetDog),
+    Cat(PetCat),
+}"#
+    ));
+    assert!(content.contains("pub struct PetDog {\n    pub bark: bool,\n}"));
+
+    // Nothing to tell the variants apart by: untagged, with a warning
+    assert!(content.contains(
+        r#"#[serde(untagged)]
+pub enum Contact {
+    Email(ContactEmail),
+    Phone(ContactPhone),
+}"#
+    ));
+    assert!(log.contains("oneOf `Contact` has no discriminator property"));
+
+    roundtrip::assert_roundtrip(
+        "cli_one_of_test",
+        "one_of_schema::OneOfSchema",
+        &serde_json::json!({
+            "shape": { "kind": "square", "side": 2.5 },
+            "pet": { "petType": "doggo", "bark": true },
+            "contact": { "phone": "+66812345678" }
+        }),
+    );
+}
@@ -0,0 +1,56 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_recursive_references_are_boxed() {
+    let schemas: Vec<&str> = vec!["linked_list.schema", "tree.schema", "mutual.schema"];
+    setup::run_generate("cli_recursive_test", schemas, &[]);
+
+    let list = common::read("tests/generated/linked_list_schema.rs");
+    assert!(list.contains("    pub head: ListNode,"));
+    assert!(list.contains("    pub next: Option<Box<ListNode>>,"));
+
+    let tree = common::read("tests/generated/tree_schema.rs");
+    assert!(tree.contains("    pub children: Vec<TreeSchema>,"));
+    assert!(tree.contains("    pub first_child: Option<Box<TreeSchema>>,"));
+    assert!(tree.contains("    pub by_name: Option<HashMap<String, TreeSchema>>,"));
+
+    let mutual = common::read("tests/generated/mutual_schema.rs");
+    assert!(mutual.contains("    pub root: Folder,"));
+    assert!(mutual.contains("    pub owner: Person,"));
+    assert!(mutual.contains("    pub home: Option<Box<Folder>>,"));
+
+    roundtrip::assert_roundtrip(
+        "cli_recursive_list_test",
+        "linked_list_schema::LinkedListSchema",
+        &serde_json::json!({
+            "head": { "value": 1, "next": { "value": 2, "next": null } }
+        }),
+    );
+    roundtrip::assert_roundtrip(
+        "cli_recursive_tree_test",
+        "tree_schema::TreeSchema",
+        &serde_json::json!({
+            "label": "root",
+            "children": [
+                { "label": "leaf", "children": [], "firstChild": null, "byName": null }
+            ],
+            "firstChild": { "label": "first", "children": [], "firstChild": null, "byName": null },
+            "byName": {}
+        }),
+    );
+    roundtrip::assert_roundtrip(
+        "cli_recursive_mutual_test",
+        "mutual_schema::MutualSchema",
+        &serde_json::json!({
+            "root": {
+                "name": "docs",
+                "owner": {
+                    "name": "kumpun",
+                    "home": { "name": "home", "owner": { "name": "kumpun", "home": null } }
+                }
+            }
+        }),
+    );
+}
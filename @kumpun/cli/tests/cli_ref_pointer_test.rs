@@ -0,0 +1,38 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_refs_resolve_by_pointer_and_anchor() {
+    let schemas: Vec<&str> = vec!["refs.schema"];
+    setup::run_generate("cli_ref_pointer_test", schemas, &[]);
+
+    let content = common::read("tests/generated/refs_schema.rs");
+    assert!(content.contains("    pub owner: Person,"));
+    assert!(content.contains("    pub backup: Option<Person>,"));
+    assert_eq!(content.matches("pub struct Person {").count(), 1);
+    assert!(content.contains("    pub first_tag: Option<String>,"));
+    assert!(content.contains("    pub level: Option<AB>,"));
+    assert!(content.contains("pub enum AB {"));
+    assert!(content.contains("    pub count: Option<u64>,"));
+    assert!(content.contains("    pub legacy: Option<Legacy>,"));
+    assert!(content.contains("    pub item: Option<Item>,"));
+    assert!(content.contains("pub struct Item {\n    pub sku: Option<String>,\n}"));
+    assert!(content.contains("    pub gadget_name: Option<String>,"));
+
+    roundtrip::assert_roundtrip(
+        "cli_ref_pointer_test",
+        "refs_schema::RefsSchema",
+        &serde_json::json!({
+            "owner": { "name": "kumpun" },
+            "backup": { "name": null },
+            "tags": ["cat"],
+            "firstTag": "cat",
+            "level": "high",
+            "count": 3,
+            "legacy": { "note": "old" },
+            "item": { "sku": "A-1" },
+            "gadgetName": "Smartwatch"
+        }),
+    );
+}
@@ -0,0 +1,43 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_rust_type_name_and_module_overrides() {
+    let schemas: Vec<&str> = vec!["overrides.schema"];
+    setup::run_generate("cli_rust_overrides_test", schemas, &[]);
+
+    // `x-rust-name` renames the root type; `x-rust-type` skips inference and imports the type
+    let content = common::read("tests/generated/overrides_schema.rs");
+    assert!(content.contains("pub struct Account {"));
+    assert!(content.contains("    pub id: NonZeroU32,"));
+    assert!(content.contains("    pub ip: Option<Ipv4Addr>,"));
+    assert!(content.contains("use std::num::NonZeroU32;"));
+    assert!(content.contains("use std::net::Ipv4Addr;"));
+    // `Ipv4Addr` is taken by the import above, so this one keeps its full path
+    assert!(content.contains("    pub next_ip: Option<core::net::Ipv4Addr>,"));
+    assert!(!content.contains("use core::net::Ipv4Addr;"));
+
+    // `x-rust-module` moves a type out of the schema's file and imports it back
+    assert!(content.contains("use crate::generated::models::postal::PostalAddress;"));
+    assert!(content.contains("use crate::generated::models::roles::AccountRole;"));
+    assert!(!content.contains("pub struct PostalAddress {"));
+
+    let postal = common::read("tests/generated/models/postal.rs");
+    assert!(postal.contains("pub struct PostalAddress {"));
+    assert!(postal.contains("use crate::generated::overrides_schema::Geo;"));
+    let roles = common::read("tests/generated/models/roles.rs");
+    assert!(roles.contains("pub enum AccountRole {"));
+
+    roundtrip::assert_roundtrip(
+        "cli_rust_overrides_test",
+        "overrides_schema::Account",
+        &serde_json::json!({
+            "address": { "city": "Bangkok", "geo": { "lat": 13.75, "lon": 100.5 } },
+            "id": 7,
+            "ip": "10.0.0.1",
+            "nextIp": "10.0.0.2",
+            "role": "admin"
+        }),
+    );
+}
@@ -0,0 +1,29 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_fixed_position_arrays_become_tuples() {
+    let schemas: Vec<&str> = vec!["tuples.schema"];
+    setup::run_generate("cli_tuple_test", schemas, &[]);
+
+    let content = common::read("tests/generated/tuples_schema.rs");
+    assert!(content.contains("    pub position: (f64, f64),"));
+    assert!(content.contains("    pub route: Option<Vec<(f64, f64)>>,"));
+    assert!(content.contains("    pub range: Option<(i32, i32)>,"));
+
+    // a trailing `items` schema collects the rest into a `Vec`
+    assert!(content.contains("    pub record: Option<Record>,"));
+    assert!(content.contains("pub struct Record(pub String, pub RecordItem2, pub Vec<bool>);"));
+
+    roundtrip::assert_roundtrip(
+        "cli_tuple_test",
+        "tuples_schema::TuplesSchema",
+        &serde_json::json!({
+            "position": [100.5, 13.75],
+            "route": [[100.5, 13.75], [100.6, 13.8]],
+            "range": [1, 10],
+            "record": ["row", { "id": "a" }, true, false]
+        }),
+    );
+}
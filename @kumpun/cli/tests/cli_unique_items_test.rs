@@ -0,0 +1,47 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_unique_items_become_sets() {
+    let schemas: Vec<&str> = vec!["unique_items.schema"];
+    let log = setup::run_generate("cli_unique_items_test", schemas.clone(), &[]);
+
+    let content = common::read("tests/generated/unique_items_schema.rs");
+    assert!(content.contains("use std::collections::BTreeSet;"));
+    assert!(content.contains("    pub tags: BTreeSet<String>,"));
+    assert!(content.contains("    pub roles: Option<BTreeSet<RolesItem>>,"));
+    assert!(content.contains("    pub cells: Option<BTreeSet<CellsItem>>,"));
+
+    // element types pick up the derives the set needs
+    assert!(content.contains(
+        "#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]\npub enum RolesItem {"
+    ));
+    assert!(content.contains(
+        "#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]\npub struct CellsItem {"
+    ));
+
+    // `f64` is neither `Eq` nor `Ord`
+    assert!(content.contains("    pub scores: Option<Vec<f64>>,"));
+    assert!(log.contains("`scores` has uniqueItems but `f64` can't derive"));
+
+    roundtrip::assert_roundtrip(
+        "cli_unique_items_test",
+        "unique_items_schema::UniqueItemsSchema",
+        &serde_json::json!({
+            "tags": ["a", "b"],
+            "roles": ["admin", "viewer"],
+            "cells": [{ "col": 0, "row": 1 }, { "col": 2, "row": 0 }],
+            "scores": [1.5, 1.5]
+        }),
+    );
+
+    setup::run_generate("cli_unique_items_test", schemas, &["--set-type", "hash"]);
+
+    let content = common::read("tests/generated/unique_items_schema.rs");
+    assert!(content.contains("use std::collections::HashSet;"));
+    assert!(content.contains("    pub tags: HashSet<String>,"));
+    assert!(content.contains(
+        "#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]\npub struct CellsItem {"
+    ));
+}
@@ -0,0 +1,102 @@
+mod common;
+mod roundtrip;
+mod setup;
+
+#[test]
+fn test_validate_derives_from_constraints() {
+    let schemas: Vec<&str> = vec!["validation.schema"];
+    setup::run_generate("cli_validation_test", schemas, &["--with-validation"]);
+
+    let content = common::read("tests/generated/validation_schema.rs");
+    assert!(content.contains("use serde::{Deserialize, Serialize};\nuse validator::Validate;"));
+    assert!(content.contains("use regex::Regex;"));
+    assert!(content.contains("use std::sync::LazyLock;"));
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ValidationSchema {"#
+    ));
+    assert!(content.contains(
+        r#"
+    #[validate(length(min = 3, max = 32), regex(path = *VALIDATION_SCHEMA_USERNAME_PATTERN))]
+    pub username: String,"#
+    ));
+    assert!(content.contains(
+        r#"static VALIDATION_SCHEMA_USERNAME_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new("^[a-z0-9_]+$").unwrap());"#
+    ));
+    assert!(content.contains("    #[validate(email)]\n    pub email: String,"));
+    assert!(content.contains("    #[validate(url)]\n    pub website: Option<String>,"));
+    assert!(content.contains("    #[validate(range(min = 13, max = 150))]\n    pub age: u8,"));
+    assert!(content.contains(
+        "    #[validate(range(exclusive_min = 0.0, exclusive_max = 100.0))]\n    pub score: Option<f64>,"
+    ));
+    // fractional and draft-04 boolean bounds: `> 1.5` on an integer is `>= 2`
+    assert!(
+        content.contains("    #[validate(range(min = 2, max = 9))]\n    pub rank: Option<u8>,")
+    );
+    assert!(content.contains(
+        "    #[validate(range(exclusive_min = 0.0, max = 1.0))]\n    pub discount: Option<f64>,"
+    ));
+    assert!(
+        content.contains(
+            "    #[validate(length(min = 1, max = 5))]\n    pub tags: Option<Vec<String>>,"
+        )
+    );
+    // a nullable type and a recursive `$ref` are validated through their wrappers
+    assert!(
+        content.contains(
+            "    #[validate(length(min = 2, max = 5))]\n    pub nickname: Option<String>,"
+        )
+    );
+    assert!(
+        content
+            .contains("    #[validate(nested)]\n    pub referrer: Option<Box<ValidationSchema>>,")
+    );
+    // `uri` maps to `Url`, which needs no string validation
+    assert!(content.contains("    pub homepage: Option<Url>,"));
+    assert!(!content.contains("    #[validate(url)]\n    pub homepage"));
+    assert!(content.contains("    #[validate(nested)]\n    pub address: Option<Address>,"));
+    assert!(content.contains(
+        r#"
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct Address {
+    #[validate(length(min = 5, max = 5))]
+    pub zip: String,
+}"#
+    ));
+
+    let valid = serde_json::json!({
+        "username": "kumpun_dev",
+        "email": "dev@kumpun.io",
+        "website": "https://kumpun.io/about",
+        "age": 30,
+        "score": 99.5,
+        "rank": 2,
+        "discount": 0.5,
+        "tags": ["api"],
+        "homepage": "https://kumpun.io/",
+        "nickname": "kp",
+        "referrer": null,
+        "address": { "zip": "10110" }
+    });
+    roundtrip::assert_roundtrip(
+        "cli_validation_test",
+        "validation_schema::ValidationSchema",
+        &valid,
+    );
+    let mut too_low = valid.clone();
+    too_low["rank"] = 1.into();
+    roundtrip::assert_invalid(
+        "cli_validation_test",
+        "validation_schema::ValidationSchema",
+        &too_low,
+    );
+    let mut bad_referrer = valid.clone();
+    bad_referrer["referrer"] = valid.clone();
+    bad_referrer["referrer"]["nickname"] = "k".into();
+    roundtrip::assert_invalid(
+        "cli_validation_test",
+        "validation_schema::ValidationSchema",
+        &bad_referrer,
+    );
+}
@@ -1,5 +1,5 @@
 use std::fs;
 
 pub fn read(path: &str) -> String {
-    fs::read_to_string(path).expect(&format!("❌ missing: {}", path))
+    fs::read_to_string(path).unwrap_or_else(|_| panic!("❌ missing: {}", path))
 }
@@ -0,0 +1,115 @@
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf, process::Command};
+
+/// Compiles `tests/generated` into a scratch crate and checks that `payload`
+/// survives a deserialize → serialize round-trip through `rust_type`.
+///
+/// `rust_type` is a path relative to the generated module root,
+/// e.g. `user_login::UserLogin`.
+pub fn assert_roundtrip(name: &str, rust_type: &str, payload: &serde_json::Value) {
+    let body = format!(
+        r#"    let parsed: generated::{} =
+        serde_json::from_value(input.clone()).expect("❌ payload did not deserialize");
+    let output = serde_json::to_value(&parsed).expect("❌ value did not serialize");
+    assert_eq!(input, output, "❌ payload changed after round-trip");"#,
+        rust_type
+    );
+    run_scratch_crate(name, rust_type, payload, &body);
+}
+
+/// Like [`assert_roundtrip`], but checks that `payload` fails to deserialize as `rust_type`.
+pub fn assert_rejects(name: &str, rust_type: &str, payload: &serde_json::Value) {
+    let body = format!(
+        r#"    let parsed = serde_json::from_value::<generated::{}>(input);
+    assert!(parsed.is_err(), "❌ payload was accepted: {{:?}}", parsed);"#,
+        rust_type
+    );
+    run_scratch_crate(name, rust_type, payload, &body);
+}
+
+/// Like [`assert_roundtrip`], but checks that `payload` deserializes as `rust_type`
+/// and then fails its `#[validate(...)]` rules.
+pub fn assert_invalid(name: &str, rust_type: &str, payload: &serde_json::Value) {
+    let body = format!(
+        r#"    let parsed: generated::{} =
+        serde_json::from_value(input).expect("❌ payload did not deserialize");
+    let result = validator::Validate::validate(&parsed);
+    assert!(result.is_err(), "❌ payload passed validation: {{:?}}", parsed);"#,
+        rust_type
+    );
+    run_scratch_crate(name, rust_type, payload, &body);
+}
+
+/// Compiles `tests/generated` and runs `body` as the scratch crate's `main`.
+pub fn assert_main(name: &str, body: &str) {
+    run_scratch_crate(name, name, &serde_json::Value::Null, body);
+}
+
+fn run_scratch_crate(name: &str, rust_type: &str, payload: &serde_json::Value, body: &str) {
+    let root = PathBuf::from(".tmp/roundtrip");
+    let crate_dir = root.join(name);
+    fs::create_dir_all(crate_dir.join("src")).unwrap();
+
+    let generated =
+        fs::canonicalize("tests/generated/mod.rs").expect("❌ missing generated mod.rs");
+
+    fs::write(
+        crate_dir.join("Cargo.toml"),
+        format!(
+            r#"[package]
+name = "{}"
+version = "0.0.0"
+edition = "2024"
+
+[dependencies]
+serde = {{ version = "1", features = ["derive"] }}
+serde_json = "1"
+chrono = {{ version = "0.4", features = ["serde"] }}
+uuid = {{ version = "1", features = ["serde"] }}
+regex = "1"
+url = {{ version = "2", features = ["serde"] }}
+validator = {{ version = "0.20", features = ["derive"] }}
+
+[workspace]
+"#,
+            name.replace('_', "-")
+        ),
+    )
+    .unwrap();
+
+    fs::write(
+        crate_dir.join("src/main.rs"),
+        format!(
+            r####"#![allow(dead_code, unused_imports)]
+
+#[path = {:?}]
+mod generated;
+
+fn main() {{
+    let input: serde_json::Value = serde_json::from_str(r###"{}"###).unwrap();
+{}
+}}
+"####,
+            generated.display(),
+            payload,
+            body
+        ),
+    )
+    .unwrap();
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = Command::new(cargo)
+        .args(["run", "--quiet", "--offline", "--manifest-path"])
+        .arg(crate_dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", root.join("target"))
+        .output()
+        .expect("Failed to run cargo");
+
+    assert!(
+        output.status.success(),
+        "Round-trip failed for {}:\n{}",
+        rust_type,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}